@@ -0,0 +1,17 @@
+use engine::bench::{bench, BENCH_DEPTH};
+
+// bench [depth], prints the node count as signature of the search
+pub fn run(args: &[String]) -> Result<(), String> {
+    let depth = match args.first() {
+        Some(depth) => depth.parse::<u8>().map_err(|_| format!("Invalid depth: {depth}"))?,
+        None => BENCH_DEPTH
+    };
+
+    let bench = bench(depth).map_err(|error| error.to_string())?;
+
+    println!("Nodes: {}", bench.nodes);
+    println!("Time: {} ms", bench.elapsed.as_millis());
+    println!("NPS: {}", bench.nps());
+
+    Ok(())
+}
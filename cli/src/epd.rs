@@ -0,0 +1,88 @@
+use std::time::Duration;
+use engine::epd::EpdPosition;
+use engine::{Engine, EngineConfig, SearchLimits};
+
+const USAGE: &str = "Usage: stockbrot epd <file> [depth <plies> | nodes <nodes> | movetime <ms>]\n\
+                     Only runs limited by depth or nodes can be compared, the default is depth 8.";
+
+// depth and node limits do not depend on the speed of the machine
+const DEFAULT_DEPTH: u8 = 8;
+
+fn parse_limits(args: &[String]) -> Result<SearchLimits, String> {
+    let [kind, value] = args else {
+        return match args.is_empty() {
+            true => Ok(SearchLimits::depth(DEFAULT_DEPTH)),
+            false => Err(USAGE.to_string())
+        };
+    };
+
+    let value = value.parse::<u64>().map_err(|_| format!("Invalid {kind}: {value}"))?;
+
+    match kind.as_str() {
+        "depth" => Ok(SearchLimits::depth(value.clamp(1, 254) as u8)),
+        "nodes" => Ok(SearchLimits::nodes(value)),
+        "movetime" => Ok(SearchLimits::move_time(Duration::from_millis(value))),
+        _ => Err(USAGE.to_string())
+    }
+}
+
+fn moves(moves: &[chess::ChessMove]) -> String {
+    moves.iter().map(|joice| joice.to_string()).collect::<Vec<String>>().join(",")
+}
+
+// epd <file> [limit], prints one line of key=value pairs per position and a total
+pub fn run(args: &[String]) -> Result<(), String> {
+    let (path, limit) = args.split_first().ok_or(USAGE)?;
+
+    let limits = parse_limits(limit)?;
+    let content = std::fs::read_to_string(path).map_err(|error| format!("Can not read {path}: {error}"))?;
+
+    // a single thread and a cleared table for every position make the results reproducible
+    let config = EngineConfig::builder().threads(1).build().expect("The epd config is valid");
+    let mut engine = Engine::new(config);
+    let (mut solved, mut total) = (0, 0);
+
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        total += 1;
+
+        let position = match EpdPosition::parse(line) {
+            Ok(position) => position,
+            Err(error) => {
+                println!("position line={} result=error error=\"{error}\"", index + 1);
+                continue;
+            }
+        };
+
+        let id = position.id.clone().unwrap_or_else(|| format!("line{}", index + 1));
+
+        engine.new_game();
+        let result = engine.load_fen(&position.fen).and_then(|_| engine.iterative_deepening(&limits, &()));
+
+        match result {
+            Ok(result) => {
+                let is_solved = position.is_solved_by(result.best_move);
+                solved += is_solved as usize;
+
+                println!(
+                    "position id={id} result={} move={} bm={} am={} depth={} nodes={} time={}",
+                    if is_solved { "solved" } else { "failed" },
+                    result.best_move,
+                    moves(&position.best_moves),
+                    moves(&position.avoid_moves),
+                    result.depth,
+                    result.nodes,
+                    result.elapsed.as_millis()
+                );
+            }
+            Err(error) => println!("position id={id} result=error error=\"{error}\"")
+        }
+    }
+
+    println!("total solved={solved} failed={} positions={total}", total - solved);
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use std::io::BufRead;
+use env_logger::Env;
+
+mod bench;
+mod epd;
+mod perft;
+mod uci;
+mod xboard;
+
+fn main() {
+    dotenv::dotenv().ok();
+
+    // stdout belongs to the protocol, logs go to stderr and are disabled by default
+    env_logger::Builder::from_env(Env::default().default_filter_or("off")).init();
+
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+
+    // subcommands for development, the protocols are spoken otherwise
+    let subcommand = match args.split_first().map(|(command, args)| (command.as_str(), args)) {
+        Some(("perft", args)) => Some(perft::run(args)),
+        Some(("bench", args)) => Some(bench::run(args)),
+        Some(("epd", args)) => Some(epd::run(args)),
+        _ => None
+    };
+
+    if let Some(result) = subcommand {
+        if let Err(error) = result {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let mut lines = std::io::stdin().lock().lines().map_while(Result::ok);
+
+    let Some(first_line) = lines.next() else { return };
+
+    // xboard compatible guis always start with the "xboard" command
+    if first_line.trim() == "xboard" {
+        xboard::run(lines);
+    } else {
+        uci::run(std::iter::once(first_line).chain(lines));
+    }
+}
@@ -0,0 +1,34 @@
+use std::str::FromStr;
+use std::time::Instant;
+use chess::Board;
+use engine::perft::divide;
+
+// perft <depth> [fen], prints the node count of every root move and the total
+pub fn run(args: &[String]) -> Result<(), String> {
+    let (depth, fen) = args.split_first().ok_or("Usage: stockbrot perft <depth> [fen]")?;
+
+    let depth = depth.parse::<u8>().ok().filter(|&depth| depth > 0).ok_or(format!("Invalid depth: {depth}"))?;
+
+    let board = match fen.join(" ").as_str() {
+        "" => Board::default(),
+        fen => Board::from_str(fen).map_err(|_| format!("Invalid fen: {fen}"))?
+    };
+
+    let start_time = Instant::now();
+    let moves = divide(&board, depth);
+    let elapsed = start_time.elapsed();
+
+    for (joice, nodes) in &moves {
+        println!("{joice}: {nodes}");
+    }
+
+    let nodes = moves.iter().map(|(_, nodes)| nodes).sum::<u64>();
+    let nps = nodes as f64 / elapsed.as_secs_f64().max(0.001);
+
+    println!();
+    println!("Nodes: {nodes}");
+    println!("Time: {} ms", elapsed.as_millis());
+    println!("NPS: {}", nps as u64);
+
+    Ok(())
+}
@@ -0,0 +1,515 @@
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use chess::{Board, Color, MoveGen};
+use engine::{Clock, Engine, EngineConfig, EngineConfigBuilder, Score, SearchLimits, SearchResult, StopHandle};
+
+#[derive(Default, Debug, PartialEq)]
+struct GoParams {
+    wtime: Option<u64>,
+    btime: Option<u64>,
+    winc: Option<u64>,
+    binc: Option<u64>,
+    movestogo: Option<u64>,
+    depth: Option<u8>,
+    nodes: Option<u64>,
+    movetime: Option<u64>,
+    mate: Option<u8>,
+    infinite: bool,
+    ponder: bool,
+}
+
+impl GoParams {
+    fn parse(args: &[&str]) -> GoParams {
+        let mut params = GoParams::default();
+
+        let mut args = args.iter();
+        while let Some(&arg) = args.next() {
+            if arg == "infinite" || arg == "ponder" {
+                params.infinite |= arg == "infinite";
+                params.ponder |= arg == "ponder";
+                continue;
+            }
+
+            let value = args.next().and_then(|value| value.parse::<u64>().ok());
+
+            match arg {
+                "wtime" => params.wtime = value,
+                "btime" => params.btime = value,
+                "winc" => params.winc = value,
+                "binc" => params.binc = value,
+                "movestogo" => params.movestogo = value,
+                "depth" => params.depth = value.map(|depth| depth.clamp(1, 254) as u8),
+                "nodes" => params.nodes = value,
+                "movetime" => params.movetime = value,
+                "mate" => params.mate = value.map(|moves| moves.clamp(1, 127) as u8),
+                _ => {}
+            }
+        }
+
+        params
+    }
+
+    fn clock(&self, color: Color) -> Option<Clock> {
+        let (time, inc) = match color {
+            Color::White => (self.wtime, self.winc),
+            Color::Black => (self.btime, self.binc)
+        };
+
+        Some(Clock {
+            time_left: Duration::from_millis(time?),
+            increment: Duration::from_millis(inc.unwrap_or(0)),
+            moves_to_go: self.movestogo.map(|movestogo| movestogo.min(u32::MAX as u64) as u32),
+        })
+    }
+
+    // a go without any limit searches for the default move time
+    fn limits(&self, color: Color, default_move_time: Duration) -> SearchLimits {
+        let clock = self.clock(color);
+        let unlimited = self.depth.is_none() && self.nodes.is_none() && self.movetime.is_none()
+            && clock.is_none() && !self.infinite && self.mate.is_none();
+
+        SearchLimits {
+            depth: self.depth,
+            nodes: self.nodes,
+            move_time: self.movetime.map(Duration::from_millis).or(unlimited.then_some(default_move_time)),
+            clock,
+            infinite: self.infinite,
+            mate: self.mate,
+            ponder: self.ponder,
+        }
+    }
+}
+
+fn uci_score(score: Score) -> String {
+    match score {
+        Score::Centipawns(centipawns) => format!("cp {centipawns}"),
+        Score::Mate(moves) => format!("mate {moves}")
+    }
+}
+
+fn print_info(info: &SearchResult) {
+    let millis = info.elapsed.as_millis() as u64;
+    let nps = info.nodes * 1000 / millis.max(1);
+
+    for (index, line) in info.lines.iter().enumerate() {
+        let pv = line.pv.iter().map(|joice| joice.to_string()).collect::<Vec<String>>().join(" ");
+
+        println!(
+            "info depth {} seldepth {} multipv {} score {} nodes {} nps {nps} time {millis} pv {pv}",
+            info.depth,
+            info.stats.seldepth,
+            index + 1,
+            uci_score(line.score),
+            info.nodes
+        );
+    }
+
+    let stats = &info.stats;
+    println!(
+        "info string qnodes {} tthits {}/{} ttcutoffs {} firstmovecutoffs {}/{} ebf {:.2}",
+        stats.qnodes,
+        stats.tt_hits,
+        stats.tt_probes,
+        stats.tt_cutoffs,
+        stats.first_move_cutoffs,
+        stats.beta_cutoffs,
+        stats.branching_factor
+    );
+}
+
+// setoption name <id> [value <x>], where both id and x may contain spaces
+fn parse_option(args: &[&str]) -> Option<(String, String)> {
+    let (&"name", args) = args.split_first()? else { return None };
+
+    let value_index = args.iter().position(|&arg| arg == "value").unwrap_or(args.len());
+
+    let name = args[..value_index].join(" ");
+    let value = args.get(value_index + 1..).map(|value| value.join(" ")).unwrap_or_default();
+
+    Some((name, value))
+}
+
+// while pondering or in infinite mode the best move may only be sent after ponderhit or stop
+#[derive(Default)]
+struct BestMove {
+    hold: bool,
+    line: Option<String>,
+}
+
+impl BestMove {
+    fn report(&mut self, line: String) {
+        if self.hold {
+            self.line = Some(line);
+        } else {
+            println!("{line}");
+        }
+    }
+
+    fn release(&mut self) {
+        self.hold = false;
+
+        if let Some(line) = self.line.take() {
+            println!("{line}");
+        }
+    }
+}
+
+struct Uci {
+    engine: Arc<Mutex<Engine>>,
+    stop_handle: StopHandle,
+    search: Option<JoinHandle<()>>,
+    best_move: Arc<Mutex<BestMove>>,
+    infinite: bool,
+    // the options set by the gui
+    config: EngineConfigBuilder,
+}
+
+fn print_options() {
+    let config = EngineConfig::default();
+
+    println!("option name Hash type spin default {} min 1 max 65536", config.hash_mb());
+    println!("option name Threads type spin default {} min 1 max 256", config.threads());
+    println!("option name OwnBook type check default {}", config.book().is_some());
+    println!("option name BookFile type string default <empty>");
+    println!("option name Move Overhead type spin default {} min 0 max 5000", config.move_overhead().as_millis());
+    println!("option name Default Move Time type spin default {} min 1 max 3600000", config.default_move_time().as_millis());
+    println!("option name Contempt type spin default {} min -1000 max 1000", config.contempt());
+    println!("option name MultiPV type spin default 1 min 1 max 256");
+    println!("option name Ponder type check default false");
+}
+
+impl Uci {
+    fn new() -> Uci {
+        let config = EngineConfig::builder();
+        let engine = Engine::new(config.build().expect("The default config is valid"));
+
+        Uci {
+            config,
+            stop_handle: engine.stop_handle(),
+            engine: Arc::new(Mutex::new(engine)),
+            search: None,
+            best_move: Arc::new(Mutex::new(BestMove::default())),
+            infinite: false,
+        }
+    }
+
+    fn position(&mut self, args: &[&str]) {
+        let mut engine = self.engine.lock().unwrap();
+
+        let moves_index = args.iter().position(|&arg| arg == "moves").unwrap_or(args.len());
+        let moves = args.get(moves_index + 1..).unwrap_or_default();
+
+        let fen = match args.first() {
+            Some(&"startpos") => None,
+            Some(&"fen") => Some(args[1..moves_index].join(" ")),
+            _ => {
+                println!("info string Invalid position command");
+                return;
+            }
+        };
+
+        if let Err(error) = engine.set_position(fen.as_deref(), moves) {
+            println!("info string {error}");
+        }
+    }
+
+    fn set_option(&mut self, args: &[&str]) {
+        let Some((name, value)) = parse_option(args) else {
+            println!("info string Invalid setoption command");
+            return;
+        };
+
+        let mut engine = self.engine.lock().unwrap();
+
+        let config = self.config.clone();
+        let config = match name.to_lowercase().as_str() {
+            "multipv" => {
+                match value.parse::<usize>() {
+                    Ok(lines) => engine.set_multi_pv(lines),
+                    Err(_) => println!("info string Invalid value for MultiPV: {value}")
+                }
+
+                return;
+            }
+            // the gui decides when to ponder
+            "ponder" => return,
+            "hash" => value.parse().ok().map(|hash_mb| config.hash_mb(hash_mb)),
+            "threads" => value.parse().ok().map(|threads| config.threads(threads)),
+            "ownbook" => value.parse().ok().map(|own_book| config.own_book(own_book)),
+            "bookfile" => {
+                let path = match value.as_str() {
+                    "" | "<empty>" => None,
+                    path => Some(Path::new(path))
+                };
+
+                // the book is only read when the file changes
+                match config.book_file(path) {
+                    Ok(config) => Some(config),
+                    Err(error) => {
+                        println!("info string {error}");
+                        return;
+                    }
+                }
+            }
+            "move overhead" => value.parse().ok().map(|millis| config.move_overhead(Duration::from_millis(millis))),
+            "default move time" => value.parse().ok().map(|millis| config.default_move_time(Duration::from_millis(millis))),
+            "contempt" => value.parse().ok().map(|contempt| config.contempt(contempt)),
+            _ => {
+                println!("info string Unknown option: {name}");
+                return;
+            }
+        };
+
+        let Some(config) = config else {
+            println!("info string Invalid value for {name}: {value}");
+            return;
+        };
+
+        match config.build() {
+            Ok(engine_config) => {
+                engine.set_config(engine_config);
+                self.config = config;
+            }
+            Err(error) => println!("info string {error}")
+        }
+    }
+
+    fn go(&mut self, args: &[&str]) {
+        let params = GoParams::parse(args);
+
+        let (limits, has_moves, book_move) = {
+            let engine = self.engine.lock().unwrap();
+
+            let board = Board::from_str(&engine.get_position()).unwrap();
+            let color = board.side_to_move();
+
+            (params.limits(color, engine.config().default_move_time()), MoveGen::new_legal(&board).len() > 0, engine.book_move())
+        };
+
+        if !has_moves {
+            println!("bestmove 0000");
+            return;
+        }
+
+        // the book is not used for analysis or while pondering on a move of the opponent
+        if let (Some(book_move), false, false) = (book_move, params.infinite, params.ponder) {
+            println!("bestmove {book_move}");
+            return;
+        }
+
+        self.infinite = params.infinite;
+        self.best_move.lock().unwrap().hold = params.infinite || params.ponder;
+
+        // a stop may arrive before the search thread is running
+        self.stop_handle.prepare_search(params.ponder);
+
+        let engine = self.engine.clone();
+        let best_move = self.best_move.clone();
+
+        self.search = Some(std::thread::spawn(move || {
+            let engine = engine.lock().unwrap();
+
+            let result = engine.iterative_deepening(&limits, &print_info);
+            engine.do_off_move_stuff();
+
+            let line = match result {
+                Ok(result) => match result.ponder_move() {
+                    Some(ponder_move) => format!("bestmove {} ponder {ponder_move}", result.best_move),
+                    None => format!("bestmove {}", result.best_move)
+                },
+                Err(error) => {
+                    println!("info string {error}");
+                    "bestmove 0000".to_string()
+                }
+            };
+
+            best_move.lock().unwrap().report(line);
+        }));
+    }
+
+    // the opponent played the expected move, the ponder search continues with its time limit
+    fn ponder_hit(&mut self) {
+        self.stop_handle.ponder_hit();
+
+        if !self.infinite {
+            self.best_move.lock().unwrap().release();
+        }
+    }
+
+    fn stop(&mut self) {
+        let Some(search) = self.search.take() else { return };
+
+        self.stop_handle.stop();
+        search.join().unwrap();
+
+        self.best_move.lock().unwrap().release();
+    }
+
+    fn handle(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        let Some((&command, args)) = tokens.split_first() else { return true };
+
+        match command {
+            "uci" => {
+                println!("id name Stockbrot {}", env!("CARGO_PKG_VERSION"));
+                println!("id author Ondolin");
+                print_options();
+                println!("uciok");
+            }
+            "isready" => println!("readyok"),
+            "setoption" => {
+                self.stop();
+                self.set_option(args);
+            }
+            "ucinewgame" => {
+                self.stop();
+                self.engine.lock().unwrap().new_game();
+            }
+            "position" => {
+                self.stop();
+                self.position(args);
+            }
+            "go" => {
+                self.stop();
+                self.go(args);
+            }
+            "ponderhit" => self.ponder_hit(),
+            "stop" => self.stop(),
+            "quit" => {
+                self.stop();
+                return false;
+            }
+            _ => log::info!("Unknown command: {line}")
+        }
+
+        true
+    }
+}
+
+pub fn run(lines: impl Iterator<Item = String>) {
+    let mut uci = Uci::new();
+
+    for line in lines {
+        if !uci.handle(line.trim()) {
+            break;
+        }
+    }
+
+    uci.stop();
+}
+
+#[test]
+fn test_parse_go() {
+    let params = GoParams::parse(&["wtime", "1000", "btime", "2000", "winc", "10", "binc", "20", "movestogo", "5"]);
+
+    assert_eq!(params, GoParams {
+        wtime: Some(1000),
+        btime: Some(2000),
+        winc: Some(10),
+        binc: Some(20),
+        movestogo: Some(5),
+        ..Default::default()
+    });
+
+    let params = GoParams::parse(&["infinite", "depth", "7", "nodes", "5000", "mate", "3"]);
+
+    assert!(params.infinite);
+    assert_eq!(params.depth, Some(7));
+    assert_eq!(params.nodes, Some(5000));
+    assert_eq!(params.mate, Some(3));
+    assert!(!params.ponder);
+
+    let params = GoParams::parse(&["ponder", "wtime", "1000", "btime", "2000"]);
+
+    assert!(params.ponder);
+    assert!(!params.infinite);
+    assert_eq!(params.btime, Some(2000));
+
+    let limits = GoParams::parse(&["wtime", "1000", "btime", "2000", "binc", "20", "movestogo", "5"]).limits(Color::Black, Duration::from_secs(5));
+
+    assert_eq!(limits.move_time, None);
+    assert_eq!(limits.clock, Some(Clock {
+        time_left: Duration::from_millis(2000),
+        increment: Duration::from_millis(20),
+        moves_to_go: Some(5),
+    }));
+
+    // only a go without any limit uses the default move time
+    assert_eq!(GoParams::parse(&[]).limits(Color::White, Duration::from_secs(5)).move_time, Some(Duration::from_secs(5)));
+    assert_eq!(GoParams::parse(&["btime", "2000"]).limits(Color::White, Duration::from_secs(5)).move_time, Some(Duration::from_secs(5)));
+
+    for args in [&["depth", "3"][..], &["nodes", "100"], &["infinite"], &["mate", "2"], &["wtime", "1000"]] {
+        assert_eq!(GoParams::parse(args).limits(Color::White, Duration::from_secs(5)).move_time, None);
+    }
+}
+
+#[test]
+fn test_parse_option() {
+    assert_eq!(parse_option(&["name", "MultiPV", "value", "3"]), Some(("MultiPV".to_string(), "3".to_string())));
+    assert_eq!(parse_option(&["name", "Clear", "Hash"]), Some(("Clear Hash".to_string(), String::new())));
+    assert_eq!(parse_option(&["MultiPV", "3"]), None);
+}
+
+#[test]
+fn test_uci_score() {
+    assert_eq!(uci_score(Score::Centipawns(-120)), "cp -120");
+    assert_eq!(uci_score(Score::Mate(2)), "mate 2");
+    assert_eq!(uci_score(Score::Mate(-1)), "mate -1");
+}
+
+#[test]
+fn test_stop_right_after_go() {
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    std::thread::spawn(move || {
+        let mut uci = Uci::new();
+
+        for line in ["position startpos moves e2e4 e7e5", "go infinite", "stop"] {
+            uci.handle(line);
+        }
+
+        sender.send(()).unwrap();
+    });
+
+    assert!(receiver.recv_timeout(Duration::from_secs(10)).is_ok());
+}
+
+#[test]
+fn test_ponder_hit_right_after_go() {
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    std::thread::spawn(move || {
+        let mut uci = Uci::new();
+
+        for line in ["position startpos moves e2e4 e7e5", "go ponder wtime 2000 btime 2000", "ponderhit"] {
+            uci.handle(line);
+        }
+
+        // the search ends by itself within the clock of 2 seconds
+        uci.search.take().unwrap().join().unwrap();
+        sender.send(()).unwrap();
+    });
+
+    assert!(receiver.recv_timeout(Duration::from_secs(2)).is_ok());
+}
+
+#[test]
+fn test_book_move() {
+    let mut uci = Uci::new();
+    uci.config = EngineConfig::builder().book_games("e2e4 e7e5").unwrap();
+    uci.handle("setoption name OwnBook value true");
+
+    // the book move is sent without a search
+    uci.handle("position startpos");
+    uci.handle("go wtime 1000 btime 1000");
+    assert!(uci.search.is_none());
+
+    uci.handle("setoption name OwnBook value false");
+    uci.handle("go wtime 1000 btime 1000");
+    assert!(uci.search.is_some());
+    uci.stop();
+}
@@ -0,0 +1,372 @@
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use chess::{Board, BoardStatus, ChessMove, Color, MoveGen};
+use engine::{Clock, Engine, Score, SearchLimits, SearchResult, StopHandle};
+
+// xboard reports mate scores as 100000 + moves to mate
+const XBOARD_MATE: i32 = 100_000;
+
+#[derive(Debug, PartialEq)]
+struct Level {
+    moves_per_session: u64,
+    base: Duration,
+    increment: Duration,
+}
+
+impl Level {
+    // level MPS BASE INC, where BASE is either minutes or minutes:seconds
+    fn parse(args: &[&str]) -> Option<Level> {
+        let [moves_per_session, base, increment] = args else { return None };
+
+        let base = match base.split_once(':') {
+            Some((minutes, seconds)) => minutes.parse::<u64>().ok()? * 60 + seconds.parse::<u64>().ok()?,
+            None => base.parse::<u64>().ok()? * 60
+        };
+
+        Some(Level {
+            moves_per_session: moves_per_session.parse().ok()?,
+            base: Duration::from_secs(base),
+            increment: Duration::try_from_secs_f64(increment.parse().ok()?).ok()?,
+        })
+    }
+}
+
+fn current_board(engine: &Engine) -> Board {
+    Board::from_str(&engine.get_position()).unwrap()
+}
+
+fn undo(engine: &mut Engine, plies: usize) {
+    for _ in 0..plies {
+        engine.undo_move();
+    }
+}
+
+fn xboard_score(score: Score) -> i32 {
+    match score {
+        Score::Centipawns(centipawns) => centipawns,
+        Score::Mate(moves) if moves > 0 => XBOARD_MATE + moves,
+        Score::Mate(moves) => -XBOARD_MATE + moves
+    }
+}
+
+fn print_thinking(info: &SearchResult) {
+    let pv = info.pv.iter().map(|joice| joice.to_string()).collect::<Vec<String>>().join(" ");
+
+    println!(
+        "{} {} {} {} {pv}",
+        info.depth,
+        xboard_score(info.score),
+        info.elapsed.as_millis() / 10,
+        info.nodes
+    );
+}
+
+fn print_result(board: &Board) {
+    match board.status() {
+        BoardStatus::Checkmate if board.side_to_move() == Color::Black => println!("1-0 {{White mates}}"),
+        BoardStatus::Checkmate => println!("0-1 {{Black mates}}"),
+        BoardStatus::Stalemate => println!("1/2-1/2 {{Stalemate}}"),
+        BoardStatus::Ongoing => {}
+    }
+}
+
+struct XBoard {
+    engine: Arc<Mutex<Engine>>,
+    stop_handle: StopHandle,
+    search: Option<JoinHandle<()>>,
+    // set if the search was interrupted by anything but ?, its move is thrown away
+    discard_move: Arc<AtomicBool>,
+    // None if the engine is in force mode
+    engine_color: Option<Color>,
+    level: Option<Level>,
+    move_time: Option<Duration>,
+    max_depth: Option<u8>,
+    time_left: Option<Duration>,
+    post: bool,
+}
+
+impl XBoard {
+    fn new() -> XBoard {
+        let engine = Engine::default();
+
+        XBoard {
+            stop_handle: engine.stop_handle(),
+            engine: Arc::new(Mutex::new(engine)),
+            search: None,
+            discard_move: Arc::new(AtomicBool::new(false)),
+            engine_color: Some(Color::Black),
+            level: None,
+            move_time: None,
+            max_depth: None,
+            time_left: None,
+            post: false,
+        }
+    }
+
+    fn clock(&self, engine: &Engine) -> Clock {
+        let (base, increment, moves_per_session) = match &self.level {
+            Some(level) => (level.base, level.increment, level.moves_per_session),
+            None => (Duration::from_secs(60), Duration::ZERO, 0)
+        };
+
+        let moves_to_go = (moves_per_session > 0).then(|| {
+            let moves_made = engine.move_history().len() as u64 / 2;
+            (moves_per_session - moves_made % moves_per_session) as u32
+        });
+
+        Clock { time_left: self.time_left.unwrap_or(base), increment, moves_to_go }
+    }
+
+    // start thinking if it is the turn of the engine
+    fn think(&mut self) {
+        let Some(engine_color) = self.engine_color else { return };
+
+        let (limits, board) = {
+            let engine = self.engine.lock().unwrap();
+
+            let limits = SearchLimits {
+                depth: self.max_depth,
+                move_time: self.move_time,
+                clock: Some(self.clock(&engine)),
+                ..Default::default()
+            };
+
+            (limits, current_board(&engine))
+        };
+
+        if board.side_to_move() != engine_color || MoveGen::new_legal(&board).len() == 0 {
+            return;
+        }
+
+        {
+            let mut engine = self.engine.lock().unwrap();
+
+            if let Some(book_move) = engine.book_move() {
+                engine.make_move(book_move.to_string()).expect("The book contains legal moves");
+
+                println!("move {book_move}");
+                return;
+            }
+        }
+
+        // a stop may arrive before the search thread is running
+        self.stop_handle.prepare_search(false);
+        self.discard_move.store(false, Ordering::SeqCst);
+
+        let engine = self.engine.clone();
+        let discard_move = self.discard_move.clone();
+        let post = self.post;
+
+        self.search = Some(std::thread::spawn(move || {
+            let mut engine = engine.lock().unwrap();
+
+            let result = engine.iterative_deepening(&limits, &|info: &SearchResult| {
+                if post { print_thinking(info) }
+            });
+
+            let best_move = match result {
+                Ok(result) => result.best_move,
+                Err(error) => {
+                    println!("# {error}");
+                    return;
+                }
+            };
+
+            if discard_move.load(Ordering::SeqCst) {
+                return;
+            }
+
+            engine.do_off_move_stuff();
+            engine.make_move(best_move.to_string()).expect("The engine plays legal moves");
+
+            println!("move {best_move}");
+            print_result(&current_board(&engine));
+        }));
+    }
+
+    // interrupt the search without playing its move
+    fn stop(&mut self) {
+        let Some(search) = self.search.take() else { return };
+
+        self.discard_move.store(true, Ordering::SeqCst);
+        self.stop_handle.stop();
+        search.join().unwrap();
+    }
+
+    fn user_move(&mut self, joice: &str) {
+        {
+            let mut engine = self.engine.lock().unwrap();
+
+            if engine.make_move(joice.to_string()).is_err() {
+                println!("Illegal move: {joice}");
+                return;
+            }
+            print_result(&current_board(&engine));
+        }
+
+        self.think();
+    }
+
+    fn set_board(&mut self, fen: &str) {
+        if self.engine.lock().unwrap().set_position(Some(fen), &[]).is_err() {
+            println!("tellusererror Illegal position");
+        }
+    }
+
+    fn handle(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        let Some((&command, args)) = tokens.split_first() else { return true };
+
+        // every command except these interrupts the search
+        if !["ping", "post", "nopost", "time", "otim", "?"].contains(&command) {
+            self.stop();
+        }
+
+        match command {
+            "protover" => println!(
+                "feature myname=\"Stockbrot {}\" usermove=1 setboard=1 ping=1 sigint=0 sigterm=0 colors=0 analyze=0 done=1",
+                env!("CARGO_PKG_VERSION")
+            ),
+            "new" => {
+                self.engine.lock().unwrap().new_game();
+
+                self.engine_color = Some(Color::Black);
+                self.max_depth = None;
+                self.time_left = None;
+            }
+            "force" => self.engine_color = None,
+            "go" => {
+                self.engine_color = Some(current_board(&self.engine.lock().unwrap()).side_to_move());
+                self.think();
+            }
+            "usermove" => match args.first() {
+                Some(joice) => self.user_move(joice),
+                None => println!("Error (missing move): usermove")
+            },
+            "level" => match Level::parse(args) {
+                Some(level) => {
+                    self.move_time = None;
+                    self.level = Some(level);
+                }
+                None => println!("Error (invalid arguments): {line}")
+            },
+            "st" => match args.first().and_then(|seconds| Duration::try_from_secs_f64(seconds.parse().ok()?).ok()) {
+                Some(move_time) => self.move_time = Some(move_time),
+                None => println!("Error (invalid arguments): {line}")
+            },
+            "sd" => match args.first().and_then(|depth| depth.parse::<u8>().ok()) {
+                Some(depth) => self.max_depth = Some(depth.max(1)),
+                None => println!("Error (invalid arguments): {line}")
+            },
+            "time" => if let Some(centiseconds) = args.first().and_then(|time| time.parse::<u64>().ok()) {
+                self.time_left = Some(Duration::from_millis(centiseconds * 10));
+            },
+            // the clock of the opponent is not used
+            "otim" => {}
+            "undo" => undo(&mut self.engine.lock().unwrap(), 1),
+            "remove" => undo(&mut self.engine.lock().unwrap(), 2),
+            "setboard" => self.set_board(&args.join(" ")),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "ping" => println!("pong {}", args.first().unwrap_or(&"")),
+            // move now, the search plays the best move it has found
+            "?" => self.stop_handle.stop(),
+            "quit" => return false,
+            "accepted" | "rejected" | "random" | "hard" | "easy" | "computer" | "result" | "name" | "rating" => {}
+            _ => {
+                // old guis send moves without the usermove prefix
+                if ChessMove::from_str(command).is_ok() {
+                    self.user_move(command);
+                } else {
+                    println!("Error (unknown command): {command}");
+                }
+            }
+        }
+
+        true
+    }
+}
+
+pub fn run(lines: impl Iterator<Item = String>) {
+    let mut xboard = XBoard::new();
+
+    for line in lines {
+        if !xboard.handle(line.trim()) {
+            break;
+        }
+    }
+
+    xboard.stop();
+}
+
+#[test]
+fn test_parse_level() {
+    assert_eq!(Level::parse(&["40", "5", "0"]), Some(Level {
+        moves_per_session: 40,
+        base: Duration::from_secs(300),
+        increment: Duration::ZERO,
+    }));
+
+    assert_eq!(Level::parse(&["0", "2:30", "1.5"]), Some(Level {
+        moves_per_session: 0,
+        base: Duration::from_secs(150),
+        increment: Duration::from_millis(1500),
+    }));
+
+    assert_eq!(Level::parse(&["0", "2"]), None);
+    assert_eq!(Level::parse(&["40", "5", "-1"]), None);
+    assert_eq!(Level::parse(&["40", "5", "NaN"]), None);
+    assert_eq!(Level::parse(&["40", "5", "1e300"]), None);
+}
+
+#[test]
+fn test_session() {
+    let mut xboard = XBoard::new();
+
+    let history = |xboard: &XBoard| xboard.engine.lock().unwrap().move_history().len();
+    let wait = |xboard: &mut XBoard| if let Some(search) = xboard.search.take() {
+        search.join().unwrap();
+    };
+
+    // a search that ends on its own plays its move
+    for line in ["new", "force", "usermove e2e4", "sd 2", "go"] {
+        assert!(xboard.handle(line));
+    }
+    wait(&mut xboard);
+    assert_eq!(history(&xboard), 2);
+
+    // every other interrupting command throws the move away
+    for interrupt in ["force", "new", "undo", "remove", "setboard 4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"] {
+        for line in ["setboard r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3", "sd 60", "go", interrupt] {
+            assert!(xboard.handle(line));
+        }
+        assert!(xboard.search.is_none());
+        assert_eq!(history(&xboard), 0);
+    }
+
+    // ? plays the best move found so far
+    for line in ["setboard r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3", "go", "?"] {
+        assert!(xboard.handle(line));
+    }
+    wait(&mut xboard);
+    assert_eq!(history(&xboard), 1);
+
+    // invalid times are rejected instead of panicking
+    for line in ["st -3", "st NaN", "st 1e300", "level 40 5 -1"] {
+        assert!(xboard.handle(line));
+    }
+    assert_eq!(xboard.move_time, None);
+
+    assert!(!xboard.handle("quit"));
+}
+
+#[test]
+fn test_xboard_score() {
+    assert_eq!(xboard_score(Score::Centipawns(-35)), -35);
+    assert_eq!(xboard_score(Score::Mate(2)), XBOARD_MATE + 2);
+    assert_eq!(xboard_score(Score::Mate(-2)), -XBOARD_MATE - 2);
+}
@@ -0,0 +1,55 @@
+use std::time::{Duration, Instant};
+use crate::{Engine, EngineConfig, EngineError, SearchLimits};
+
+pub const BENCH_DEPTH: u8 = 4;
+
+// opening, middle game and endgame positions with tactics, promotions and mates
+const BENCH_POSITIONS: [&str; 8] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+    "3q1rk1/5ppp/2n2n2/p1pNb3/3pP3/3P3N/PPbB2PP/R3KB1R b KQ - 1 16",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "8/8/4k3/8/2p5/8/B1K5/8 w - - 0 1",
+    "8/P7/8/8/8/8/6k1/K7 w - - 0 1",
+];
+
+/// Outcome of a bench run. The node count only changes if the search does.
+#[derive(Clone, Debug)]
+pub struct Bench {
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+impl Bench {
+    pub fn nps(&self) -> u64 {
+        (self.nodes as f64 / self.elapsed.as_secs_f64().max(0.001)) as u64
+    }
+}
+
+/// Search a fixed set of positions to a fixed depth. Every position is searched
+/// by a new engine with a single thread, so the node count is deterministic.
+pub fn bench(depth: u8) -> Result<Bench, EngineError> {
+    let config = EngineConfig::builder()
+        .hash_mb(16)
+        .threads(1)
+        .own_book(false)
+        .build()?;
+
+    let mut nodes = 0;
+    let mut elapsed = Duration::ZERO;
+
+    for fen in BENCH_POSITIONS {
+        let mut engine = Engine::new(config.clone());
+        engine.load_fen(fen)?;
+
+        let start_time = Instant::now();
+        let result = engine.iterative_deepening(&SearchLimits::depth(depth), &())?;
+        elapsed += start_time.elapsed();
+
+        nodes += result.nodes;
+    }
+
+    Ok(Bench { nodes, elapsed })
+}
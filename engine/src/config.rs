@@ -0,0 +1,311 @@
+use std::path::Path;
+use std::str::FromStr;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use chess::{Board, ChessMove};
+use opening_db::NODE_MAP;
+use opening_db_types::{BuildNode, Node as OpeningDBNode};
+use crate::EngineError;
+
+#[derive(Clone, Debug)]
+enum BookTree {
+    Builtin(OpeningDBNode),
+    File(Arc<BuildNode>),
+}
+
+/// Opening book of the engine, either compiled in or loaded from a file
+#[derive(Clone, Debug)]
+pub struct Book {
+    tree: BookTree,
+    // line from the initial position to every book position by its hash, built when first needed
+    lines: Arc<OnceLock<HashMap<u64, Vec<ChessMove>>>>,
+}
+
+impl Book {
+    fn new(tree: BookTree) -> Book {
+        Book { tree, lines: Arc::default() }
+    }
+
+    /// Most played move after the given moves from the initial position, None if they leave the book
+    pub fn probe(&self, moves: &[ChessMove]) -> Option<ChessMove> {
+        let joice = match &self.tree {
+            BookTree::Builtin(root) => {
+                let mut node = root.clone();
+                for joice in moves {
+                    node = node.get_node_by_move(joice.to_string())?;
+                }
+
+                node.get_best_move()?
+            }
+            BookTree::File(root) => {
+                let mut node: &BuildNode = root;
+                for joice in moves {
+                    node = node.get_node_by_move(&joice.to_string())?;
+                }
+
+                node.get_best_move()?
+            }
+        };
+
+        Some(ChessMove::from_str(&joice).expect("The book contains only valid moves"))
+    }
+
+    /// Moves from the initial position to the given position, None if the book does not contain it
+    pub fn line_to(&self, board: &Board) -> Option<&[ChessMove]> {
+        // the index is not needed for games from the initial position
+        if *board == Board::default() {
+            return Some(&[]);
+        }
+
+        let lines = self.lines.get_or_init(|| {
+            let mut lines = HashMap::new();
+
+            match &self.tree {
+                BookTree::Builtin(root) => index_lines(root, &|node: &OpeningDBNode| {
+                    node.children.iter().map(|child| (child.1.joice, &child.1)).collect()
+                }, Board::default(), &mut vec![], &mut lines),
+                BookTree::File(root) => index_lines(root.as_ref(), &|node: &BuildNode| {
+                    node.children.iter().map(|child| (child.1.joice.as_str(), &child.1)).collect()
+                }, Board::default(), &mut vec![], &mut lines),
+            }
+
+            lines
+        });
+
+        lines.get(&board.get_hash()).map(Vec::as_slice)
+    }
+}
+
+// remember the first line that reaches each position of the book
+fn index_lines<'a, N>(
+    node: &'a N,
+    children: &impl Fn(&'a N) -> Vec<(&'a str, &'a N)>,
+    board: Board,
+    line: &mut Vec<ChessMove>,
+    lines: &mut HashMap<u64, Vec<ChessMove>>,
+) {
+    lines.entry(board.get_hash()).or_insert_with(|| line.clone());
+
+    for (joice, child) in children(node) {
+        let chess_move = ChessMove::from_str(joice).expect("The book contains only valid moves");
+
+        line.push(chess_move);
+        index_lines(child, children, board.make_move_new(chess_move), line, lines);
+        line.pop();
+    }
+}
+
+/// Settings of an engine, created with `EngineConfig::builder`
+#[derive(Clone, Debug)]
+pub struct EngineConfig {
+    hash_mb: usize,
+    threads: usize,
+    // None if the book is disabled
+    book: Option<Book>,
+    default_move_time: Duration,
+    move_overhead: Duration,
+    contempt: i32,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        EngineConfig::builder().build().expect("The default config is valid")
+    }
+}
+
+impl EngineConfig {
+    pub fn builder() -> EngineConfigBuilder {
+        EngineConfigBuilder::default()
+    }
+
+    pub fn hash_mb(&self) -> usize {
+        self.hash_mb
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn book(&self) -> Option<&Book> {
+        self.book.as_ref()
+    }
+
+    /// Move time of a search that is given no other limit, e.g. a plain `go` or a game without a clock
+    pub fn default_move_time(&self) -> Duration {
+        self.default_move_time
+    }
+
+    /// Time lost between the end of the search and the arrival of the move at the server
+    pub fn move_overhead(&self) -> Duration {
+        self.move_overhead
+    }
+
+    /// Centipawns the engine is willing to give up to avoid a draw
+    pub fn contempt(&self) -> i32 {
+        self.contempt
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EngineConfigBuilder {
+    hash_mb: usize,
+    threads: usize,
+    own_book: bool,
+    // read when the file is set, building the config does not read it again
+    book: Option<Arc<BuildNode>>,
+    default_move_time: Duration,
+    move_overhead: Duration,
+    contempt: i32,
+}
+
+impl Default for EngineConfigBuilder {
+    fn default() -> Self {
+        EngineConfigBuilder {
+            hash_mb: 256,
+            threads: std::thread::available_parallelism().map(|threads| threads.get()).unwrap_or(1),
+            own_book: true,
+            book: None,
+            default_move_time: Duration::from_secs(5),
+            move_overhead: Duration::from_millis(50),
+            contempt: 0,
+        }
+    }
+}
+
+impl EngineConfigBuilder {
+    /// Size of the transposition table
+    pub fn hash_mb(mut self, hash_mb: usize) -> Self {
+        self.hash_mb = hash_mb;
+        self
+    }
+
+    /// Number of threads searching the root moves in parallel
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    pub fn own_book(mut self, own_book: bool) -> Self {
+        self.own_book = own_book;
+        self
+    }
+
+    /// Book file with one game per line, written as uci moves. The compiled in book is used without a path.
+    /// The file is read immediately.
+    pub fn book_file(mut self, path: Option<&Path>) -> Result<Self, EngineError> {
+        self.book = match path {
+            Some(path) => {
+                let games = std::fs::read_to_string(path)
+                    .map_err(|error| EngineError::UnreadableFile { path: path.display().to_string(), error: error.to_string() })?;
+
+                Some(Arc::new(parse_book(&games)?))
+            }
+            None => None
+        };
+        Ok(self)
+    }
+
+    /// Book with one game per line, in the format of a book file
+    pub fn book_games(mut self, games: &str) -> Result<Self, EngineError> {
+        self.book = Some(Arc::new(parse_book(games)?));
+        Ok(self)
+    }
+
+    pub fn default_move_time(mut self, default_move_time: Duration) -> Self {
+        self.default_move_time = default_move_time;
+        self
+    }
+
+    pub fn move_overhead(mut self, move_overhead: Duration) -> Self {
+        self.move_overhead = move_overhead;
+        self
+    }
+
+    pub fn contempt(mut self, contempt: i32) -> Self {
+        self.contempt = contempt;
+        self
+    }
+
+    pub fn build(&self) -> Result<EngineConfig, EngineError> {
+        if self.hash_mb == 0 {
+            return Err(EngineError::InvalidConfig { name: "Hash".to_string(), value: self.hash_mb.to_string() });
+        }
+
+        if self.threads == 0 {
+            return Err(EngineError::InvalidConfig { name: "Threads".to_string(), value: self.threads.to_string() });
+        }
+
+        let book = match (&self.book, self.own_book) {
+            (_, false) => None,
+            (Some(book), true) => Some(Book::new(BookTree::File(book.clone()))),
+            (None, true) => Some(Book::new(BookTree::Builtin(NODE_MAP))),
+        };
+
+        Ok(EngineConfig {
+            hash_mb: self.hash_mb,
+            threads: self.threads,
+            book,
+            default_move_time: self.default_move_time,
+            move_overhead: self.move_overhead,
+            contempt: self.contempt,
+        })
+    }
+}
+
+fn parse_book(games: &str) -> Result<BuildNode, EngineError> {
+    let mut root = BuildNode::new(String::new());
+
+    for line in games.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        let mut board = Board::default();
+        let mut node = &mut root;
+
+        for joice in line.split_whitespace() {
+            let chess_move = ChessMove::from_str(joice).ok()
+                .filter(|chess_move| board.legal(*chess_move))
+                .ok_or_else(|| EngineError::InvalidConfig { name: "BookFile".to_string(), value: line.to_string() })?;
+
+            board = board.make_move_new(chess_move);
+            node = node.add_child(BuildNode::new(joice.to_string()));
+        }
+    }
+
+    Ok(root)
+}
+
+#[test]
+fn test_build_config() {
+    let config = EngineConfig::builder().hash_mb(16).threads(2).contempt(20).build().unwrap();
+
+    assert_eq!(config.hash_mb(), 16);
+    assert_eq!(config.threads(), 2);
+    assert_eq!(config.contempt(), 20);
+    assert!(config.book().is_some());
+
+    assert!(EngineConfig::builder().own_book(false).build().unwrap().book().is_none());
+    assert!(EngineConfig::builder().threads(0).build().is_err());
+    assert!(matches!(
+        EngineConfig::builder().book_file(Some(Path::new("/does/not/exist"))),
+        Err(EngineError::UnreadableFile { path, .. }) if path == "/does/not/exist"
+    ));
+}
+
+#[test]
+fn test_parse_book() {
+    let book = parse_book("# two games\ne2e4 e7e5 g1f3\ne2e4 c7c5\n\nd2d4 d7d5\n").unwrap();
+    assert_eq!(book.get_best_move(), Some("e2e4".to_string()));
+    assert_eq!(book.get_node_by_move("e2e4").unwrap().children.len(), 2);
+
+    let book = Book::new(BookTree::File(Arc::new(book)));
+    let e2e4 = ChessMove::from_str("e2e4").unwrap();
+    assert_eq!(book.probe(&[]), Some(e2e4));
+    assert_eq!(book.probe(&[e2e4]), Some(ChessMove::from_str("e7e5").unwrap()));
+    assert_eq!(book.probe(&[ChessMove::from_str("a2a3").unwrap()]), None);
+
+    // positions are found by their hash, whatever the move order
+    let board = Board::from_str("rnbqkbnr/ppp1pppp/8/3p4/3P4/8/PPP1PPPP/RNBQKBNR w KQkq - 0 2").unwrap();
+    assert_eq!(book.line_to(&board).map(<[ChessMove]>::len), Some(2));
+    assert_eq!(book.line_to(&Board::default().make_move_new(ChessMove::from_str("a2a3").unwrap())), None);
+
+    assert!(parse_book("e2e4 e2e4\n").is_err());
+}
@@ -0,0 +1,120 @@
+use std::str::FromStr;
+use chess::{Board, ChessMove};
+use crate::EngineError;
+
+/// A test position of an EPD file, e.g. `<fen> bm Qg6; id "WAC.001";`
+#[derive(Clone, Debug, PartialEq)]
+pub struct EpdPosition {
+    /// The four fen fields of the position with default move counters
+    pub fen: String,
+    pub id: Option<String>,
+    /// One of these moves has to be played
+    pub best_moves: Vec<ChessMove>,
+    /// None of these moves may be played
+    pub avoid_moves: Vec<ChessMove>,
+}
+
+// split the operations at semicolons, which are not part of a string
+fn operations(text: &str) -> Vec<&str> {
+    let mut operations = Vec::new();
+    let mut in_string = false;
+    let mut start = 0;
+
+    for (index, char) in text.char_indices() {
+        match char {
+            '"' => in_string = !in_string,
+            ';' if !in_string => {
+                operations.push(text[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    operations.push(text[start..].trim());
+    operations.retain(|operation| !operation.is_empty());
+
+    operations
+}
+
+// moves of test suites are written in san, but uci notation is accepted too
+fn parse_move(board: &Board, text: &str) -> Result<ChessMove, EngineError> {
+    let san = text.trim_end_matches(['+', '#', '!', '?']);
+
+    ChessMove::from_san(board, san)
+        .or_else(|_| ChessMove::from_str(san))
+        .ok()
+        .filter(|&joice| board.legal(joice))
+        .ok_or_else(|| EngineError::UnparsableMove(text.to_string()))
+}
+
+impl EpdPosition {
+    pub fn parse(line: &str) -> Result<EpdPosition, EngineError> {
+        let fields = line.split_whitespace().take(4).collect::<Vec<&str>>();
+
+        if fields.len() < 4 {
+            return Err(EngineError::InvalidFen(line.to_string()));
+        }
+
+        let fen = format!("{} 0 1", fields.join(" "));
+        let board = Board::from_str(&fen).map_err(|_| EngineError::InvalidFen(fen.clone()))?;
+
+        // the operations start after the fourth field
+        let rest = line.trim_start();
+        let rest = fields.iter().fold(rest, |rest, field| rest[field.len()..].trim_start());
+
+        let mut position = EpdPosition { fen, id: None, best_moves: Vec::new(), avoid_moves: Vec::new() };
+
+        for operation in operations(rest) {
+            let (opcode, operands) = operation.split_once(char::is_whitespace).unwrap_or((operation, ""));
+
+            match opcode {
+                "bm" | "am" => {
+                    let moves = operands.split_whitespace()
+                        .map(|joice| parse_move(&board, joice))
+                        .collect::<Result<Vec<ChessMove>, EngineError>>()?;
+
+                    if opcode == "bm" { position.best_moves = moves } else { position.avoid_moves = moves }
+                }
+                "id" => position.id = Some(operands.trim().trim_matches('"').to_string()),
+                // other opcodes like comments are not needed to judge the move
+                _ => {}
+            }
+        }
+
+        if position.best_moves.is_empty() && position.avoid_moves.is_empty() {
+            return Err(EngineError::InvalidEpd(line.to_string()));
+        }
+
+        Ok(position)
+    }
+
+    pub fn is_solved_by(&self, joice: ChessMove) -> bool {
+        (self.best_moves.is_empty() || self.best_moves.contains(&joice)) && !self.avoid_moves.contains(&joice)
+    }
+}
+
+#[test]
+fn test_parse_epd() {
+    let position = EpdPosition::parse("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";").unwrap();
+
+    assert_eq!(position.fen, "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1");
+    assert_eq!(position.id.as_deref(), Some("WAC.001"));
+    assert_eq!(position.best_moves, vec![ChessMove::from_str("g3g6").unwrap()]);
+    assert!(position.is_solved_by(ChessMove::from_str("g3g6").unwrap()));
+    assert!(!position.is_solved_by(ChessMove::from_str("g1h1").unwrap()));
+
+    // several moves, checks and a semicolon inside a comment
+    let position = EpdPosition::parse("r1b1k2r/ppppnppp/2n2q2/2b5/3NP3/2P1B3/PP3PPP/RN1QKB1R w KQkq - am Nxc6 Bb5; bm Qd2 Nb3+; c0 \"a; b\"; id \"test\";").unwrap();
+
+    assert_eq!(position.avoid_moves.len(), 2);
+    assert_eq!(position.best_moves.len(), 2);
+    assert_eq!(position.id.as_deref(), Some("test"));
+    assert!(!position.is_solved_by(ChessMove::from_str("d4c6").unwrap()));
+    assert!(position.is_solved_by(ChessMove::from_str("d1d2").unwrap()));
+
+    assert!(EpdPosition::parse("8/8/8/8/8/8/8/8 w -").is_err());
+    // without bm or am every move would count as solved
+    assert!(matches!(EpdPosition::parse("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - id \"WAC.001\";"), Err(EngineError::InvalidEpd(_))));
+    assert!(EpdPosition::parse("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qh8;").is_err());
+}
@@ -0,0 +1,33 @@
+use std::fmt;
+
+/// Errors of the public engine api, caused by invalid input or configuration
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EngineError {
+    InvalidFen(String),
+    /// The move is not written in uci notation, e.g. e2e4 or e7e8q, or in san in epd files
+    UnparsableMove(String),
+    IllegalMove(String),
+    /// The game is over, there is no move to search
+    NoLegalMoves,
+    /// An epd test position without a bm or am operation, it can not be judged
+    InvalidEpd(String),
+    /// A file named in the configuration, e.g. the book file, does not exist or can not be read
+    UnreadableFile { path: String, error: String },
+    InvalidConfig { name: String, value: String },
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::InvalidFen(fen) => write!(f, "invalid fen: {fen}"),
+            EngineError::UnparsableMove(joice) => write!(f, "can not parse move: {joice}"),
+            EngineError::IllegalMove(joice) => write!(f, "illegal move: {joice}"),
+            EngineError::NoLegalMoves => write!(f, "there are no legal moves in this position"),
+            EngineError::InvalidEpd(line) => write!(f, "epd position without bm or am: {line}"),
+            EngineError::UnreadableFile { path, error } => write!(f, "can not read {path}: {error}"),
+            EngineError::InvalidConfig { name, value } => write!(f, "invalid value for {name}: {value}"),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
@@ -0,0 +1,117 @@
+use chess::{BitBoard, Board, Color, File, get_file, Piece, Rank, Square};
+
+const DARK_SQUARES: BitBoard = BitBoard(0xAA55_AA55_AA55_AA55);
+
+fn minor_pieces(board: &Board, color: Color) -> BitBoard {
+    (board.pieces(Piece::Knight) | board.pieces(Piece::Bishop)) & board.color_combined(color)
+}
+
+fn has_major_pieces_or_pawns(board: &Board) -> bool {
+    (board.pieces(Piece::Pawn) | board.pieces(Piece::Rook) | board.pieces(Piece::Queen)).popcnt() > 0
+}
+
+// No sequence of legal moves can lead to a mate: KvK, KNvK, KBvK and bishops of a single square color
+pub fn is_dead_position(board: &Board) -> bool {
+    if has_major_pieces_or_pawns(board) { return false }
+
+    let minors = minor_pieces(board, Color::White) | minor_pieces(board, Color::Black);
+    if minors.popcnt() <= 1 { return true }
+
+    // only bishops, which all move on the same color
+    let bishops = *board.pieces(Piece::Bishop);
+    let dark_bishops = (bishops & DARK_SQUARES).popcnt();
+
+    minors == bishops && (dark_bishops == 0 || dark_bishops == bishops.popcnt())
+}
+
+// A mate is possible, but can not be forced against correct defense
+pub fn is_theoretical_draw(board: &Board) -> bool {
+    if is_wrong_bishop_fortress(board, Color::White) || is_wrong_bishop_fortress(board, Color::Black) {
+        return true;
+    }
+
+    if has_major_pieces_or_pawns(board) { return false }
+
+    let white = minor_pieces(board, Color::White);
+    let black = minor_pieces(board, Color::Black);
+
+    // a single minor piece on each side
+    if white.popcnt() <= 1 && black.popcnt() <= 1 { return true }
+
+    // two knights against the bare king
+    let knights = *board.pieces(Piece::Knight);
+    (white == knights && white.popcnt() == 2 && black.popcnt() == 0)
+        || (black == knights && black.popcnt() == 2 && white.popcnt() == 0)
+}
+
+// Bishop and rook pawns, where the bishop does not control the promotion square
+// and the defending king has reached the corner
+fn is_wrong_bishop_fortress(board: &Board, attacker: Color) -> bool {
+    let defender = !attacker;
+
+    let attacking = board.color_combined(attacker);
+    let pawns = board.pieces(Piece::Pawn) & attacking;
+    let bishops = board.pieces(Piece::Bishop) & attacking;
+
+    // only king, bishop and pawns for the attacker, a bare king for the defender
+    if bishops.popcnt() != 1 || pawns.popcnt() == 0 { return false }
+    if attacking.popcnt() != pawns.popcnt() + 2 || board.color_combined(defender).popcnt() != 1 { return false }
+
+    let file = if pawns & get_file(File::A) == pawns {
+        File::A
+    } else if pawns & get_file(File::H) == pawns {
+        File::H
+    } else {
+        return false
+    };
+
+    let rank = if attacker == Color::White { Rank::Eighth } else { Rank::First };
+    let promotion = Square::make_square(rank, file);
+
+    let promotion_is_dark = DARK_SQUARES & BitBoard::from_square(promotion) != BitBoard(0);
+    let bishop_is_dark = bishops & DARK_SQUARES != BitBoard(0);
+
+    if promotion_is_dark == bishop_is_dark { return false }
+
+    let king = board.king_square(defender);
+    let distance = (king.get_file().to_index() as i32 - file.to_index() as i32).abs()
+        .max((king.get_rank().to_index() as i32 - rank.to_index() as i32).abs());
+
+    distance <= 1
+}
+
+#[test]
+fn test_dead_position() {
+    use std::str::FromStr;
+
+    let dead = |fen: &str| is_dead_position(&Board::from_str(fen).unwrap());
+
+    assert!(dead("8/8/4k3/8/8/3K4/8/8 w - - 0 1"));
+    assert!(dead("8/8/4k3/8/8/3K4/5N2/8 w - - 0 1"));
+    assert!(dead("8/8/4k3/8/8/3K4/5B2/8 w - - 0 1"));
+    // bishops on light squares only
+    assert!(dead("8/5b2/4k3/8/8/3K4/6B1/8 w - - 0 1"));
+
+    assert!(!dead("8/8/4kb2/8/8/3K4/6B1/8 w - - 0 1"));
+    assert!(!dead("8/8/4k3/8/8/3K4/5NN1/8 w - - 0 1"));
+    assert!(!dead("8/8/4k3/8/8/3K4/5P2/8 w - - 0 1"));
+}
+
+#[test]
+fn test_theoretical_draw() {
+    use std::str::FromStr;
+
+    let draw = |fen: &str| is_theoretical_draw(&Board::from_str(fen).unwrap());
+
+    assert!(draw("8/8/4kn2/8/8/3K4/5B2/8 w - - 0 1"));
+    assert!(draw("8/8/4k3/8/8/3K4/5NN1/8 w - - 0 1"));
+    // the h8 square is dark, the bishop light
+    assert!(draw("7k/8/7P/8/8/3K4/8/5B2 w - - 0 1"));
+    assert!(draw("8/8/8/8/8/2k5/p7/K4b2 b - - 0 1"));
+
+    // the bishop controls the promotion square
+    assert!(!draw("7k/8/7P/8/8/3K4/8/4B3 w - - 0 1"));
+    // the defending king is too far away
+    assert!(!draw("8/8/7P/8/8/3K4/1k6/5B2 w - - 0 1"));
+    assert!(!draw("8/8/4k3/8/8/3K4/5BB1/8 w - - 0 1"));
+}
@@ -1,12 +1,21 @@
 use chess::{ALL_SQUARES, Board, BoardStatus, Color, Piece};
-use crate::evaluation::{game_phase_inc, MATE_SCORE, mg_value, eg_value, piece_mobility, connected_bonus, double_isolated};
+use crate::evaluation::{game_phase_inc, DRAW_SCORE, MATE_SCORE, mg_value, eg_value, piece_mobility, connected_bonus, double_isolated};
+use crate::evaluation::{is_dead_position, is_theoretical_draw};
 
 pub fn evaluate(board: &Board) -> i32 {
+    evaluate_with_draw_score(board, DRAW_SCORE)
+}
+
+// the search scores draws with the contempt of the engine, the score is white relative like the evaluation
+pub fn evaluate_with_draw_score(board: &Board, draw_score: i32) -> i32 {
 
     let game_status = board.status();
-    if game_status == BoardStatus::Stalemate { return 0 }
+    if game_status == BoardStatus::Stalemate { return draw_score }
     if game_status == BoardStatus::Checkmate { return if board.side_to_move() == Color::Black { MATE_SCORE } else { -MATE_SCORE } }
 
+    // no side can win, even if it has more material
+    if is_dead_position(board) || is_theoretical_draw(board) { return draw_score }
+
     score_board(board)
 
 }
@@ -18,6 +27,11 @@ const fn color_multiplier(color: &Color) -> i32 {
     }
 }
 
+// connected pawns only gain in the end game once they reach the 6th rank, the rank is seen from the pawn's side
+fn connected_eg_scale(transposed_rank: usize) -> i32 {
+    (transposed_rank as i32 - 2) / 4
+}
+
 fn score_board(board: &Board) -> i32 {
     // sum in mid game
     let mut mg_score: i32 = 0;
@@ -59,7 +73,7 @@ fn score_board(board: &Board) -> i32 {
                 let other_pawns = if color == Color::White { &black_pawns } else { &white_pawns };
 
                 // Connectivity
-                let bonus = connected_bonus(&my_pawns, &other_pawns, square, color);
+                let bonus = connected_bonus(my_pawns, other_pawns, square, color);
 
                 let transposed_rank = if color == Color::White {
                     square.get_rank().to_index()
@@ -68,11 +82,11 @@ fn score_board(board: &Board) -> i32 {
                 };
 
                 mg_score += color_multiplier(&color) * bonus;
-                eg_score += color_multiplier(&color) * bonus * ((transposed_rank - 2) / 4) as i32;
+                eg_score += color_multiplier(&color) * bonus * connected_eg_scale(transposed_rank);
 
 
                 // Isolated
-                let isolation = double_isolated(&my_pawns, square);
+                let isolation = double_isolated(my_pawns, square);
                 if isolation >= 2 {
                     mg_score -= color_multiplier(&color) * 11;
                     eg_score -= color_multiplier(&color) * 56;
@@ -98,7 +112,6 @@ fn score_board(board: &Board) -> i32 {
 #[bench]
 fn evaluation_speed(b: &mut test::Bencher) {
     use std::str::FromStr;
-    use chess::ChessMove;
 
     let board = Board::from_str("3q1rk1/5ppp/2n2n2/p1pNb3/3pP3/3P3N/PPbB2PP/R3KB1R b KQ - 1 16").unwrap();
     //let board = board.make_move_new(ChessMove::from_str("a6a8").unwrap());
@@ -106,4 +119,13 @@ fn evaluation_speed(b: &mut test::Bencher) {
     b.iter(|| {
         evaluate(&board);
     });
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_connected_eg_scale() {
+    // pawns on their 2nd rank are not scaled
+    assert_eq!(connected_eg_scale(1), 0);
+
+    assert_eq!(connected_eg_scale(5), 0);
+    assert_eq!(connected_eg_scale(6), 1);
+}
@@ -1,3 +1,4 @@
+mod draw;
 mod evaluate;
 mod piece_sq_tables;
 mod mobility;
@@ -6,8 +7,10 @@ mod helper;
 
 pub const MATE_SCORE: i32 = 1_000_000;
 pub const CONSIDERED_MATE: i32 = MATE_SCORE - 10_000;
+pub const DRAW_SCORE: i32 = 0;
 
-pub use evaluate::evaluate;
+pub use draw::{is_dead_position, is_theoretical_draw};
+pub use evaluate::{evaluate, evaluate_with_draw_score};
 pub use piece_sq_tables::{mg_value, eg_value, game_phase_inc};
 pub use mobility::piece_mobility;
 pub use pawn::connectivity::connected_bonus;
@@ -15,12 +15,10 @@ fn supporter(all_my_pawns: &BitBoard, field: Square, color: Color) -> i32 {
     };
 
     // check left and right
-    for field in [field.left(), field.right()] {
-        // ignore if the pawn is an outer pawn
-        if let Some(field) = field {
-            if all_my_pawns & BitBoard::from_square(field) != BitBoard(0) {
-                counter += 1;
-            }
+    // outer pawns only have one neighbour
+    for field in [field.left(), field.right()].into_iter().flatten() {
+        if all_my_pawns & BitBoard::from_square(field) != BitBoard(0) {
+            counter += 1;
         }
     }
 
@@ -31,12 +29,10 @@ fn supporter(all_my_pawns: &BitBoard, field: Square, color: Color) -> i32 {
 fn phalanx(all_my_pawns: &BitBoard, field: Square) -> i32 {
 
     // check left and right
-    for field in [field.left(), field.right()] {
-        // ignore if the pawn is an outer pawn
-        if let Some(field) = field {
-            if all_my_pawns & BitBoard::from_square(field) != BitBoard(0) {
-                return 1;
-            }
+    // outer pawns only have one neighbour
+    for field in [field.left(), field.right()].into_iter().flatten() {
+        if all_my_pawns & BitBoard::from_square(field) != BitBoard(0) {
+            return 1;
         }
     }
 
@@ -64,7 +60,7 @@ pub fn connected_bonus(all_my_pawns: &BitBoard, all_other_pawns: &BitBoard, fiel
 
     if supporter == 0 && phalanx == 0 { return 0; }
 
-    let opposed = opposed(&all_other_pawns, field, color);
+    let opposed = opposed(all_other_pawns, field, color);
 
     let transposed_rank = if color == Color::White {
         field.get_rank().to_index()
@@ -78,7 +74,7 @@ pub fn connected_bonus(all_my_pawns: &BitBoard, all_other_pawns: &BitBoard, fiel
 #[test]
 fn test_supporter() {
     use std::str::FromStr;
-    use chess::{ChessMove, ALL_SQUARES};
+    use chess::{ALL_SQUARES, Board, Piece};
 
     let board = Board::from_str("rnbqkbnr/1p2p1pp/p4p2/8/3P4/2P1p3/PP4PP/RNBQKBNR w KQkq - 0 4").unwrap();
 
@@ -110,7 +106,7 @@ fn test_supporter() {
 #[test]
 fn test_phalanx() {
     use std::str::FromStr;
-    use chess::{ChessMove, ALL_SQUARES};
+    use chess::{ALL_SQUARES, Board, Piece};
 
     let board = Board::from_str("rnbqkbnr/1p2p1pp/p4p2/8/3P4/4p1P1/PPP4P/RNBQKBNR b KQkq - 0 4").unwrap();
 
@@ -142,7 +138,7 @@ fn test_phalanx() {
 #[test]
 fn test_opposed() {
     use std::str::FromStr;
-    use chess::{ChessMove, ALL_SQUARES};
+    use chess::{ALL_SQUARES, Board, Piece};
 
     let board = Board::from_str("rnb1kbnr/1p2pp1p/p5p1/8/8/P1P5/P2PP1PP/RNBQKBNR w KQkq - 0 2").unwrap();
 
@@ -174,7 +170,7 @@ fn test_opposed() {
 #[test]
 fn test_bonus() {
     use std::str::FromStr;
-    use chess::{ChessMove, ALL_SQUARES};
+    use chess::{ALL_SQUARES, Board, Piece};
 
     let board = Board::from_str("rnb1kbnr/2P1pp1p/1P1P2p1/1p6/2p5/8/3P1PPP/RNBQKBNR b KQkq - 0 2").unwrap();
 
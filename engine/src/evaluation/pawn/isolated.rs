@@ -25,7 +25,7 @@ pub fn double_isolated(all_my_pawns: &BitBoard, field: Square) -> i32 {
 #[test]
 fn test_isolated() {
     use std::str::FromStr;
-    use chess::{ChessMove, ALL_SQUARES, Board, Piece, Color};
+    use chess::{Board, Piece, Color};
 
     let board = Board::from_str("rnb1kbnr/2P1pp1p/1P1P2p1/p7/2p5/8/3P1PPP/RNBQKBNR b KQkq - 0 2").unwrap();
 
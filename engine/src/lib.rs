@@ -3,130 +3,377 @@ extern crate test;
 
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
-use chess::{ChessMove, Color, Game};
+use chess::{Board, ChessMove, Color, Game};
 
-use opening_db::NODE_MAP;
-use opening_db_types::Node as OpeningDBNode;
-
-use crate::search::SearchData;
+use crate::search::{SearchData, SearchPath};
 
+mod config;
+mod error;
+pub mod bench;
+pub mod epd;
 pub mod evaluation;
+pub mod perft;
 mod search;
 mod transposition_table;
 
+pub use config::{Book, EngineConfig, EngineConfigBuilder};
+pub use error::EngineError;
+pub use search::{Clock, PvLine, ReductionTable, Score, SearchLimits, SearchObserver, SearchResult, SearchStats, StopHandle, TimeManager, WindowFail};
+
 pub struct Engine {
+    config: EngineConfig,
+    thread_pool: rayon::ThreadPool,
     game: Game,
+    start_position: Board,
+    // moves made since the start position
+    history: Vec<ChessMove>,
     search_data: Arc<SearchData>,
-    opening_db_node: Option<OpeningDBNode>,
-    moves_made: u16,
+    // half moves played before the start position, taken from the fen
+    start_ply: u16,
+    // positions of the game, used to find repetitions
+    game_path: SearchPath,
+    multi_pv: usize,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new(EngineConfig::default())
+    }
+}
+
+fn build_thread_pool(threads: usize) -> rayon::ThreadPool {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .expect("Could not create the search threads")
 }
 
 impl Engine {
-    pub fn new() -> Engine {
-        let e = Engine {
+    pub fn new(config: EngineConfig) -> Engine {
+        Engine {
+            thread_pool: build_thread_pool(config.threads()),
             game: Game::new(),
-            search_data: Arc::new(SearchData::new()),
-            opening_db_node: Some(NODE_MAP),
-            moves_made: 0
-        };
+            start_position: Board::default(),
+            history: Vec::new(),
+            search_data: Arc::new(SearchData::new(config.hash_mb())),
+            config,
+            start_ply: 0,
+            game_path: SearchPath::new(&Board::default(), 0),
+            multi_pv: 1,
+        }
+    }
+
+    /// Start a new game from the initial position. The transposition table and the move ordering
+    /// heuristics are cleared, the config and the analysis settings are kept.
+    pub fn new_game(&mut self) {
+        self.load_start_position();
+        self.thread_pool.install(|| self.search_data.clear());
+    }
+
+    // continue from the initial position, the search keeps what it has learned
+    fn load_start_position(&mut self) {
+        self.game = Game::new();
+        self.start_position = Board::default();
+        self.history.clear();
+        self.start_ply = 0;
+        self.game_path = SearchPath::new(&self.start_position, 0);
+    }
+
+    pub fn config(&self) -> &EngineConfig {
+        &self.config
+    }
+
+    /// Apply a new config. The transposition table is only cleared if its size changes.
+    pub fn set_config(&mut self, config: EngineConfig) {
+        if config.threads() != self.config.threads() {
+            self.thread_pool = build_thread_pool(config.threads());
+        }
+
+        if config.hash_mb() != self.config.hash_mb() {
+            let mut search_data = SearchData::new(config.hash_mb());
+            search_data.stop = self.search_data.stop.clone();
+            search_data.reductions = self.search_data.reductions.clone();
+
+            self.search_data = Arc::new(search_data);
+        }
 
-        e.search_data.visit_position(&e.game.current_position());
+        self.config = config;
+    }
+
+    /// Handle to stop the search of this engine from another thread
+    pub fn stop_handle(&self) -> StopHandle {
+        self.search_data.stop.clone()
+    }
 
-        e
+    /// Late move reductions of the search, they can be changed for tuning
+    pub fn reduction_table(&self) -> &ReductionTable {
+        &self.search_data.reductions
+    }
+
+    // number of best root moves which are reported with their score and pv
+    pub fn set_multi_pv(&mut self, lines: usize) {
+        self.multi_pv = lines.max(1);
     }
 
     pub fn get_position(&self) -> String {
         self.game.current_position().to_string()
     }
 
-    pub fn load_fen(&mut self, fen: &str) -> Result<(), ()> {
-        let game = Game::from_str(fen);
-        self.opening_db_node = None;
-        match game {
-            Ok(game) => {
-                self.game = game;
-                self.search_data.visit_position(&self.game.current_position());
-                Ok(())
-            },
-            Err(_) => Err(())
+    pub fn load_fen(&mut self, fen: &str) -> Result<(), EngineError> {
+        let game = Game::from_str(fen).map_err(|_| EngineError::InvalidFen(fen.to_string()))?;
+
+        self.start_position = game.current_position();
+        self.history.clear();
+        let halfmove_clock = fen.split_whitespace().nth(4).and_then(|clock| clock.parse().ok()).unwrap_or(0);
+        let fullmove_number = fen.split_whitespace().nth(5).and_then(|number| number.parse::<u16>().ok()).unwrap_or(1);
+        let black_to_move = game.side_to_move() == Color::Black;
+        self.start_ply = fullmove_number.saturating_sub(1).saturating_mul(2) + black_to_move as u16;
+        self.game_path = SearchPath::new(&self.start_position, halfmove_clock);
+        self.game = game;
+
+        Ok(())
+    }
+
+    /// Set up a position the way the protocols send it: a fen, or the start position if there is none, followed by moves.
+    /// The moves become part of the game history. If the fen or a move is invalid, nothing is changed.
+    pub fn set_position(&mut self, fen: Option<&str>, moves: &[&str]) -> Result<(), EngineError> {
+        let mut board = match fen {
+            Some(fen) => Board::from_str(fen).map_err(|_| EngineError::InvalidFen(fen.to_string()))?,
+            None => Board::default()
+        };
+
+        // check all moves before the game is changed
+        for joice in moves {
+            let chess_move = ChessMove::from_str(joice).map_err(|_| EngineError::UnparsableMove(joice.to_string()))?;
+
+            if !board.legal(chess_move) {
+                return Err(EngineError::IllegalMove(joice.to_string()));
+            }
+
+            board = board.make_move_new(chess_move);
+        }
+
+        match fen {
+            Some(fen) => self.load_fen(fen)?,
+            None => self.load_start_position()
         }
+
+        for joice in moves {
+            self.make_move(joice.to_string())?;
+        }
+
+        Ok(())
     }
 
-    pub fn make_move(&mut self, joice: String) {
+    pub fn make_move(&mut self, joice: String) -> Result<(), EngineError> {
 
-        if let Some(opening_node) = &mut self.opening_db_node {
-            self.opening_db_node = opening_node.get_node_by_move(joice.clone());
+        let chess_move = ChessMove::from_str(&joice).map_err(|_| EngineError::UnparsableMove(joice.clone()))?;
+
+        if !self.game.current_position().legal(chess_move) {
+            return Err(EngineError::IllegalMove(joice));
         }
 
-        // here only my own moves are counted
-        if self.opening_db_node.is_none() {
-            self.moves_made += 1;
+        self.history.push(chess_move);
+
+        log::info!("Made move {:?}", chess_move.to_string());
+
+        let position = self.game.current_position();
+        self.game.make_move(chess_move);
+
+        self.game_path.push(&position, chess_move, &self.game.current_position());
+
+        Ok(())
+    }
+
+    /// Take back the last move, returns None if no move has been made since the start position
+    pub fn undo_move(&mut self) -> Option<ChessMove> {
+        let joice = self.history.pop()?;
+
+        self.game_path.pop();
+
+        // the game can not go back, so it is replayed without the last move
+        self.game = Game::new_with_board(self.start_position);
+        for previous in &self.history {
+            self.game.make_move(*previous);
         }
 
-        let joice = ChessMove::from_str(&joice).expect("No valid Chess move...");
+        log::info!("Took back move {joice}");
 
-        log::info!("Made move {:?}", joice.to_string());
+        Some(joice)
+    }
 
-        self.game.make_move(joice);
+    /// Moves made since the start position or the last loaded fen
+    pub fn move_history(&self) -> Vec<ChessMove> {
+        self.history.clone()
+    }
+
+    /// Half moves played in the game, including the ones before a loaded fen
+    pub fn game_ply(&self) -> u16 {
+        self.start_ply.saturating_add(self.history.len() as u16)
+    }
 
-        self.search_data.visit_position(&self.game.current_position());
+    /// Plies since the last capture or pawn move, as used by the fifty move rule
+    pub fn halfmove_clock(&self) -> u16 {
+        self.game_path.halfmove_clock()
     }
 
-    pub fn get_engine_move(&mut self, timeout: Duration) -> String {
+    /// Move of the opening book for the current position, None if the game has left the book or the book is disabled.
+    /// A position set up by a fen is looked up in the book, then the book is followed along the moves of the game.
+    pub fn book_move(&self) -> Option<ChessMove> {
+        let book = self.config.book()?;
+
+        let mut moves = book.line_to(&self.start_position)?.to_vec();
+        moves.extend(&self.history);
+
+        book.probe(&moves)
+    }
+
+    pub fn get_engine_move(&mut self, limits: &SearchLimits) -> Result<SearchResult, EngineError> {
         log::info!("Generating move...");
 
-       if let Some(opening_book) = &self.opening_db_node {
-            if let Some(opening_move) = opening_book.get_best_move() {
-                log::info!("Opening DB move: {opening_move}");
-                return opening_move;
-            }
+        if let Some(joice) = self.book_move() {
+            log::info!("Opening DB move: {joice}");
+
+            return Ok(SearchResult::book_move(joice));
         }
 
-        let joice = self.iterative_deepening(self.search_data.clone(), timeout).to_string();
+        let result = self.iterative_deepening(limits, &())?;
 
-        log::warn!("Engine Move: {joice}");
-        log::warn!("Pos: {}", self.game.current_position().to_string());
+        log::warn!("Engine Move: {} ({:?}, depth {}, {} nodes in {:?})", result.best_move, result.score, result.depth, result.nodes, result.elapsed);
+        log::warn!("Pos: {}", self.game.current_position());
 
-        joice
+        Ok(result)
     }
 
     pub fn is_my_turn(&self, color: Color) -> bool {
         self.game.side_to_move() == color
     }
 
-    pub fn recommended_timeout(&self, time_remaining: Duration, increase: Duration) -> Duration {
+    pub fn do_off_move_stuff(&self) {
 
-        let default_timeout = Duration::from_secs(dotenv::var("DEFAULT_TIMEOUT_SECS").unwrap().parse::<u64>().unwrap());
+        self.thread_pool.install(|| self.search_data.transposition_table.age_table());
+        self.search_data.previous_score.lock().unwrap().clear();
 
-        // Time = inf
-        if time_remaining.as_secs() > 2_000_000 {
-            return default_timeout;
-        }
+    }
+}
 
-        let n_moves = self.moves_made.min(10);
-        let factor = 2 - n_moves / 10;
 
-        let potential_moves_left = 100 - self.moves_made.min(80);
-        assert!(potential_moves_left >= 10);
+#[test]
+fn test_undo_move() {
+    let mut engine = Engine::default();
+    let start = engine.get_position();
 
-        let target = time_remaining.as_millis() as u64 / potential_moves_left as u64;
+    assert_eq!(engine.undo_move(), None);
 
-        let recommended_timeout = Duration::from_millis(target * factor as u64) + increase;
+    // knights go back and forth, the start position is reached for the third time
+    for joice in ["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8"] {
+        engine.make_move(joice.to_string()).unwrap();
+    }
 
-        if recommended_timeout.as_secs() >= 5 {
-            default_timeout
-        } else {
-            recommended_timeout
-        }
+    assert_eq!(engine.move_history().len(), 8);
+    assert_eq!(engine.game_path.repetitions(), 2);
 
-    }
+    assert_eq!(engine.undo_move(), Some(ChessMove::from_str("f6g8").unwrap()));
+    assert_eq!(engine.undo_move(), Some(ChessMove::from_str("f3g1").unwrap()));
+    assert_eq!(engine.move_history().len(), 6);
 
-    pub fn do_off_move_stuff(&self) {
+    // the position after Nf3 Nf6 has been reached twice
+    assert_eq!(engine.game_path.repetitions(), 1);
 
-        self.search_data.transposition_table.age_table();
-        self.search_data.previous_score.lock().unwrap().clear();
+    engine.make_move("f3g1".to_string()).unwrap();
+    engine.make_move("f6g8".to_string()).unwrap();
+    assert_eq!(engine.game_path.repetitions(), 2);
 
+    for _ in 0..8 {
+        engine.undo_move();
     }
+
+    assert_eq!(engine.get_position(), start);
+    assert!(engine.move_history().is_empty());
+    assert_eq!(engine.game_path.repetitions(), 0);
+
+    engine.make_move("e2e4".to_string()).unwrap();
+    engine.make_move("a7a6".to_string()).unwrap();
+    engine.undo_move();
+    assert_eq!(engine.game_ply(), 1);
 }
 
+#[test]
+fn test_set_position() {
+    let mut engine = Engine::default();
+
+    engine.set_position(Some("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"), &["f1c4", "g8f6", "f3g5"]).unwrap();
+    assert_eq!(engine.move_history().len(), 3);
+    assert_eq!(engine.halfmove_clock(), 5);
+    // the fen starts at move 3 with white to move
+    assert_eq!(engine.game_ply(), 7);
+
+    engine.make_move("d7d5".to_string()).unwrap();
+    assert_eq!(engine.halfmove_clock(), 0);
+    engine.undo_move();
+    assert_eq!(engine.halfmove_clock(), 5);
+
+    // an illegal move does not change anything
+    let position = engine.get_position();
+    assert_eq!(engine.set_position(None, &["e2e4", "e2e4"]), Err(EngineError::IllegalMove("e2e4".to_string())));
+    assert_eq!(engine.get_position(), position);
+
+    // the repetition table contains the replayed moves
+    engine.set_position(None, &["g1f3", "g8f6", "f3g1", "f6g8"]).unwrap();
+    assert_eq!(engine.game_path.repetitions(), 1);
+}
+
+#[test]
+fn test_book_follows_the_moves() {
+    let mut engine = Engine::new(EngineConfig::builder().book_games("e2e4 e7e5 g1f3\ne2e4 c7c5").unwrap().build().unwrap());
+
+    let g1f3 = ChessMove::from_str("g1f3").ok();
+
+    engine.set_position(None, &["e2e4", "e7e5"]).unwrap();
+    assert_eq!(engine.book_move(), g1f3);
+
+    // the initial position given as a fen uses the book as well
+    engine.set_position(Some("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), &["e2e4", "e7e5"]).unwrap();
+    assert_eq!(engine.book_move(), g1f3);
+
+    // a book position set up by a fen is found as well
+    engine.set_position(Some("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2"), &[]).unwrap();
+    assert_eq!(engine.book_move(), g1f3);
+
+    engine.set_position(Some("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"), &["e7e5"]).unwrap();
+    assert_eq!(engine.book_move(), g1f3);
+
+    // a move outside of the book leaves it
+    engine.set_position(None, &["e2e4", "a7a6", "d2d4"]).unwrap();
+    assert_eq!(engine.book_move(), None);
+
+    // the book is left and entered again with the moves
+    engine.undo_move();
+    engine.undo_move();
+    assert_eq!(engine.book_move(), ChessMove::from_str("e7e5").ok());
+
+    // a disabled book is not used any more
+    engine.set_config(EngineConfig::builder().own_book(false).build().unwrap());
+    assert_eq!(engine.book_move(), None);
+}
+
+#[test]
+fn test_new_game() {
+    let mut engine = Engine::new(EngineConfig::builder().hash_mb(16).threads(1).build().unwrap());
+    engine.set_multi_pv(3);
+    engine.reduction_table().set(4, 10, 0);
+
+    let fresh = engine.iterative_deepening(&SearchLimits::depth(4), &()).unwrap();
+    // the second search profits from the filled transposition table
+    assert!(engine.iterative_deepening(&SearchLimits::depth(4), &()).unwrap().nodes < fresh.nodes);
+
+    engine.make_move("e2e4".to_string()).unwrap();
+    engine.new_game();
+    assert!(engine.move_history().is_empty());
+
+    // nothing learned is kept, but the analysis settings and the tuning are
+    let result = engine.iterative_deepening(&SearchLimits::depth(4), &()).unwrap();
+    assert_eq!(result.nodes, fresh.nodes);
+    assert_eq!(result.lines.len(), 3);
+    assert_eq!(engine.reduction_table().reduction(4, 10), 0);
+}
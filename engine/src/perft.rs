@@ -0,0 +1,46 @@
+use chess::{Board, ChessMove, MoveGen};
+
+/// Count the leaf nodes of the legal move tree, used to validate the move generation
+pub fn perft(board: &Board, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = MoveGen::new_legal(board);
+
+    // the moves of the last ply only need to be counted
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves.map(|joice| perft(&board.make_move_new(joice), depth - 1)).sum()
+}
+
+/// Perft split by the root moves, to find the line in which the count differs from a reference.
+/// Depth 0 has no root moves, the result is empty.
+pub fn divide(board: &Board, depth: u8) -> Vec<(ChessMove, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    MoveGen::new_legal(board)
+        .map(|joice| (joice, perft(&board.make_move_new(joice), depth - 1)))
+        .collect()
+}
+
+#[test]
+fn test_divide() {
+    use std::str::FromStr;
+
+    let board = Board::default();
+    let moves = divide(&board, 2);
+
+    assert_eq!(moves.len(), 20);
+    assert!(moves.iter().all(|&(_, nodes)| nodes == 20));
+    assert_eq!(moves.iter().map(|&(_, nodes)| nodes).sum::<u64>(), perft(&board, 2));
+
+    let moves = divide(&board, 1);
+    assert!(moves.contains(&(ChessMove::from_str("e2e4").unwrap(), 1)));
+
+    assert!(divide(&board, 0).is_empty());
+}
@@ -1,14 +1,15 @@
+use std::cmp::Reverse;
 use std::fmt;
-use std::sync::{Arc, RwLock};
-use std::sync::atomic::Ordering;
-use chess::{Board, BoardStatus, ChessMove, Color, MoveGen};
+use std::sync::{Arc, Mutex};
+use chess::{Board, BoardStatus, ChessMove, EMPTY, MoveGen, Piece};
 use crate::Engine;
-use crate::search::quiesce_search::{quiesce_search_max, quiesce_search_min};
-use crate::search::move_order::get_move_order;
+use crate::search::quiesce_search::{evaluate_relative, quiesce_search};
+use crate::search::move_order::{get_move_order, is_capture};
+use crate::search::pv::{complete_pv, update_pv};
 
 use rayon::prelude::*;
-use crate::evaluation::{CONSIDERED_MATE, MATE_SCORE};
-use crate::search::{NodeType, SearchData, STOP_THREADS};
+use crate::evaluation::{is_dead_position, CONSIDERED_MATE, MATE_SCORE};
+use crate::search::{NodeType, Score, SearchData, SearchObserver, SearchPath, WindowFail};
 
 const WINDOW_SIZE: i32 = 100 / 4;
 const DOUBLE_WINDOW_SIZE: i32 = WINDOW_SIZE * 2;
@@ -16,6 +17,12 @@ const QUADRUPLE_WINDOW_SIZE: i32 = WINDOW_SIZE * 4;
 
 const MAX_WINDOW_SIZE: i32 = 2 * MATE_SCORE + 10;
 
+// larger than any score a search can return
+const INFINITE_SCORE: i32 = MATE_SCORE + 1;
+
+// null moves are only tried with enough depth left to save work
+const NULL_MOVE_MIN_DEPTH: u8 = 3;
+
 struct AspirationWindow {
     source: i32,
     left: i32,
@@ -48,15 +55,16 @@ impl AspirationWindow {
         }
     }
 
-    pub fn enlarge_and_check_bound(&mut self, value: i32) -> bool {
+    // returns the side of the window the value fell out of
+    pub fn enlarge_and_check_bound(&mut self, value: i32) -> Option<WindowFail> {
         if value <= self.alpha() {
             self.left = Self::next(self.left);
-            false
+            Some(WindowFail::Low)
         } else if value >= self.beta() {
             self.right = Self::next(self.right);
-            false
+            Some(WindowFail::High)
         } else {
-            true
+            None
         }
     }
 
@@ -75,33 +83,45 @@ impl fmt::Display for AspirationWindow {
     }
 }
 
+// A move at the root together with its exact score and principal variation
+pub struct RootMove {
+    pub pv: Vec<ChessMove>,
+    pub score: i32,
+}
+
 impl Engine {
-    pub fn alpha_beta_search(&self, max_depth: u8, search_data: Arc<SearchData>) -> (Option<ChessMove>, i32) {
+    // returns all root moves which have been searched completely, the best move first.
+    // The path contains the game up to the root.
+    pub fn alpha_beta_search(&self, root: &Board, path: &SearchPath, max_depth: u8, search_data: Arc<SearchData>, observer: &dyn SearchObserver) -> Vec<RootMove> {
 
         {
             search_data.best_moves.clear();
         }
 
-        let best_move: RwLock<(Option<ChessMove>, i32)> =
-            RwLock::new((
-                None,
-                if self.game.side_to_move() == Color::White { i32::MIN } else { i32::MAX }
-            ));
+        let root_moves: Mutex<Vec<RootMove>> = Mutex::new(Vec::new());
 
-        let moves = MoveGen::new_legal(&self.game.current_position()).collect::<Vec<ChessMove>>();
+        let moves = MoveGen::new_legal(root).collect::<Vec<ChessMove>>();
 
-        if moves.len() == 1 { return (Some(moves[0]), 0) }
+        if moves.len() == 1 { return vec![RootMove { pv: moves, score: 0 }] }
 
-        moves.par_iter().for_each(|joice| {
+        self.thread_pool.install(|| moves.par_iter().for_each(|joice| {
 
-            if STOP_THREADS.load(Ordering::SeqCst) { return; }
+            if search_data.stop.is_hard_stopped() { return; }
 
-            let copy = self.game.current_position().make_move_new(*joice);
+            let copy = root.make_move_new(*joice);
             let board_hash = copy.get_hash();
 
-            // prevent repetition of moves
-            if self.search_data.position_visited_twice(&copy) {
-                log::info!("Can not play {} due to repetition of moves", joice.to_string());
+            let mut path = path.clone();
+            path.push(root, *joice, &copy);
+
+            // repetitions, the fifty move rule and dead positions end the game immediately
+            if path.is_draw(&copy) || is_dead_position(&copy) {
+                log::info!("{} leads to a draw", joice);
+
+                let score = search_data.draw_score(root.side_to_move());
+
+                observer.root_move_scored(max_depth, *joice, Score::relative(score));
+                root_moves.lock().unwrap().push(RootMove { pv: vec![*joice], score });
                 return;
             }
 
@@ -113,55 +133,45 @@ impl Engine {
                 }
             };
 
-            loop {
-
-                if STOP_THREADS.load(Ordering::SeqCst) { return; }
-
-                if self.game.side_to_move() == Color::White {
-
-                    let (score, _) = alpha_beta_min(copy, window.alpha(), window.beta(), max_depth - 1, search_data.clone());
-
-                    if !window.enlarge_and_check_bound(score) {
-                        // window was to small
-                        continue;
-                    }
+            let mut line: Vec<ChessMove> = Vec::new();
 
-                    if STOP_THREADS.load(Ordering::SeqCst) { return; }
-
-                    log::info!("Move Evaluation: {} {score}", joice.to_string());
+            loop {
 
-                    { self.search_data.previous_score.lock().unwrap().insert(board_hash, score); }
+                if search_data.stop.is_hard_stopped() { return; }
 
-                    if score >= { best_move.read().unwrap().1 } {
-                        *best_move.write().unwrap() = (Some(*joice), score);
-                    }
-                } else {
-                    let (score, _) = alpha_beta_max(copy, window.alpha(), window.beta(), max_depth - 1, search_data.clone());
+                let (score, _) = alpha_beta(copy, -window.beta(), -window.alpha(), max_depth - 1, search_data.clone(), &mut path, &mut line);
+                let score = -score;
 
-                    if !window.enlarge_and_check_bound(score) {
-                        // window was to small
-                        continue;
-                    }
+                if let Some(fail) = window.enlarge_and_check_bound(score) {
+                    // window was to small
+                    observer.window_failed(max_depth, *joice, fail);
+                    continue;
+                }
 
-                    if STOP_THREADS.load(Ordering::SeqCst) { return; }
+                if search_data.stop.is_hard_stopped() { return; }
 
-                    log::info!("Move Evaluation: {} {score}", joice.to_string());
+                log::info!("Move Evaluation: {} {score}", joice);
+                observer.root_move_scored(max_depth, *joice, Score::relative(score));
 
-                    { self.search_data.previous_score.lock().unwrap().insert(board_hash, score); }
+                { self.search_data.previous_score.lock().unwrap().insert(board_hash, score); }
 
-                    if score <= { best_move.read().unwrap().1 } {
-                        *best_move.write().unwrap() = (Some(*joice), score);
-                    }
-                }
+                let mut pv = Vec::new();
+                update_pv(&mut pv, *joice, &line);
+                root_moves.lock().unwrap().push(RootMove { pv, score });
 
                 break;
             }
 
-        });
+        }));
+
+        let mut root_moves = root_moves.into_inner().unwrap();
+        root_moves.sort_by_key(|root_move| Reverse(root_move.score));
 
-        let best_move = *best_move.read().unwrap();
+        for root_move in root_moves.iter_mut() {
+            complete_pv(root, &mut root_move.pv, &search_data, max_depth as usize);
+        }
 
-        best_move
+        root_moves
     }
 
 }
@@ -177,87 +187,150 @@ fn consider_short_mate(value: i32) -> i32 {
     }
 }
 
-pub fn alpha_beta_max(board: Board, mut alpha: i32, beta: i32, depth_left: u8, search_data: Arc<SearchData>) -> (i32, NodeType) {
-    // leaf node
-    if depth_left == 0 || board.status() != BoardStatus::Ongoing {
-        return quiesce_search_max(board, alpha, beta);
-    }
-
-    let mut best_move: Option<ChessMove> = None;
+// Zugzwang is likely if the side to move has only pawns or a single minor piece,
+// then passing would be better than any move and null move pruning fails.
+fn has_null_move_material(board: &Board) -> bool {
+    let own = board.color_combined(board.side_to_move());
 
-    for joice in get_move_order(&board, search_data.clone()) {
+    let majors = (board.pieces(Piece::Rook) | board.pieces(Piece::Queen)) & own;
+    let minors = (board.pieces(Piece::Knight) | board.pieces(Piece::Bishop)) & own;
 
-        if STOP_THREADS.load(Ordering::SeqCst) { break; }
+    majors != EMPTY || minors.popcnt() >= 2
+}
 
-        let copy = board.make_move_new(joice);
+// Passing is only tried with enough depth left to save work and when it can not be the best move.
+// There is no null move while in check, passing twice in a row would only waste time.
+fn allows_null_move(board: &Board, depth_left: u8, path: &SearchPath) -> bool {
+    depth_left >= NULL_MOVE_MIN_DEPTH && *board.checkers() == EMPTY && !path.is_after_null_move() && has_null_move_material(board)
+}
 
-        let score = SearchData::get_or_calculate(
-            search_data.clone(),
-            copy.get_hash(),
-            alpha,
-            beta,
-            depth_left - 1,
-            |data| alpha_beta_min(copy, alpha, beta, depth_left - 1, data)
-        );
+// deeper searches are reduced more
+fn null_move_reduction(depth_left: u8) -> u8 {
+    2 + depth_left / 4
+}
 
-        // Score is outside of the window
-        if score >= beta { return (beta, NodeType::CUT) }
+// search a child through the transposition table, the window is seen from the child
+fn search_child(child: Board, alpha: i32, beta: i32, depth_left: u8, search_data: &Arc<SearchData>, path: &mut SearchPath, line: &mut Vec<ChessMove>) -> i32 {
+    SearchData::get_or_calculate(
+        search_data.clone(),
+        child.get_hash(),
+        alpha,
+        beta,
+        depth_left,
+        |data| alpha_beta(child, alpha, beta, depth_left, data, path, line)
+    )
+}
 
-        // Make window smaller
-        if score > alpha {
-            alpha = score;
-            best_move = Some(joice);
-        }
+// Fail-soft negamax, scores are relative to the side to move.
+// The node type tells if the score is exact or only a bound of the true score.
+// Nodes searched with an open window are PV nodes, all others are searched with a null window (Principal Variation Search).
+pub fn alpha_beta(board: Board, mut alpha: i32, beta: i32, depth_left: u8, search_data: Arc<SearchData>, path: &mut SearchPath, pv: &mut Vec<ChessMove>) -> (i32, NodeType) {
+    pv.clear();
 
+    // leaf node, it is counted by the quiescence search
+    if depth_left == 0 || board.status() != BoardStatus::Ongoing {
+        return quiesce_search(board, alpha, beta, path.ply(), &search_data);
     }
 
-    if let Some(best_move) = best_move { // node is PV
-        search_data.best_moves.insert(board.get_hash(), best_move);
-        (consider_short_mate(alpha), NodeType::PV)
-    } else { // node is bound
-        (alpha, NodeType::ALL)
-    }
+    search_data.visit_node(path.ply());
 
-}
+    let is_pv_node = beta - alpha > 1;
+    let in_check = *board.checkers() != EMPTY;
 
-pub fn alpha_beta_min(board: Board, alpha: i32, mut beta: i32, depth_left: u8, search_data: Arc<SearchData>) -> (i32, NodeType) {
-    // leaf node
-    if depth_left == 0 || board.status() != BoardStatus::Ongoing {
-        return quiesce_search_min(board, alpha, beta);
+    // Null move pruning: if the position is still too good for the opponent after passing, a real move will be too.
+    if search_data.features.null_move_pruning && !is_pv_node && allows_null_move(&board, depth_left, path) && evaluate_relative(&board, &search_data) >= beta {
+        if let Some(passed) = board.null_move() {
+            let depth = (depth_left - 1).saturating_sub(null_move_reduction(depth_left));
+
+            path.push_null_move(&passed);
+            let score = -search_child(passed, -beta, -beta + 1, depth, &search_data, path, &mut Vec::new());
+            path.pop();
+
+            if score >= beta {
+                // a mate found with a null move is not proven
+                return (if score.abs() > CONSIDERED_MATE { beta } else { score }, NodeType::CUT);
+            }
+        }
     }
 
+    let mut best_score = -INFINITE_SCORE;
     let mut best_move: Option<ChessMove> = None;
 
-    for joice in get_move_order(&board, search_data.clone()) {
+    let ply = path.ply();
+    let previous = path.last_move();
+
+    // quiet moves which did not cause a cutoff lose history score
+    let mut quiet_moves: Vec<ChessMove> = Vec::new();
+
+    for (index, joice) in get_move_order(&board, search_data.clone(), ply, previous).into_iter().enumerate() {
 
-        if STOP_THREADS.load(Ordering::SeqCst) { break; }
+        if search_data.stop.is_hard_stopped() { break; }
 
         let copy = board.make_move_new(joice);
+        let mut line: Vec<ChessMove> = Vec::new();
+        let is_quiet = !is_capture(&board, joice) && joice.get_promotion().is_none();
+
+        path.push(&board, joice, &copy);
+
+        // draws depend on the path, so they are detected before the transposition table is used
+        let score = if path.is_draw(&copy) || is_dead_position(&copy) {
+            search_data.draw_score(board.side_to_move())
+        } else if index == 0 || !search_data.features.principal_variation_search {
+            -search_child(copy, -beta, -alpha, depth_left - 1, &search_data, path, &mut line)
+        } else {
+            // late quiet moves are unlikely to be good, they are searched less deep first
+            let is_tactical = !is_quiet || in_check || *copy.checkers() != EMPTY;
+            let is_killer = search_data.heuristics.is_killer(ply, joice);
+            let reduction = if is_pv_node || is_tactical || is_killer { 0 } else { search_data.reductions.reduction(depth_left, index) };
+
+            // prove with a null window that the move is worse than the first one
+            let mut score = -search_child(copy, -alpha - 1, -alpha, depth_left - 1 - reduction, &search_data, path, &mut line);
+
+            if reduction > 0 && score > alpha {
+                score = -search_child(copy, -alpha - 1, -alpha, depth_left - 1, &search_data, path, &mut line);
+            }
+
+            // in a pv node a better move needs an exact score
+            if is_pv_node && score > alpha && score < beta {
+                -search_child(copy, -beta, -alpha, depth_left - 1, &search_data, path, &mut line)
+            } else {
+                score
+            }
+        };
 
-        let score = SearchData::get_or_calculate(
-            search_data.clone(),
-            copy.get_hash(),
-            alpha,
-            beta,
-            depth_left - 1,
-            |data| alpha_beta_max(copy, alpha, beta, depth_left - 1, data)
-        );
+        path.pop();
+
+        best_score = best_score.max(score);
+
+        // the opponent will avoid this position, the score is a lower bound
+        if score >= beta {
+            search_data.stats.beta_cutoff(index);
+
+            if is_quiet && !search_data.stop.is_hard_stopped() {
+                search_data.heuristics.update(board.side_to_move(), ply, depth_left, previous, joice, &quiet_moves);
+            }
+
+            return (consider_short_mate(score), NodeType::CUT)
+        }
 
-        // Score is outside of the window
-        if score <= alpha { return (alpha, NodeType::CUT) }
+        if is_quiet {
+            quiet_moves.push(joice);
+        }
 
         // Make window smaller
-        if score < beta {
-            beta = score;
+        if score > alpha {
+            alpha = score;
             best_move = Some(joice);
+            update_pv(pv, joice, &line);
         }
+
     }
 
     if let Some(best_move) = best_move { // node is PV
         search_data.best_moves.insert(board.get_hash(), best_move);
-        (consider_short_mate(beta), NodeType::PV)
-    } else { // node is bound
-        (beta, NodeType::ALL)
+        (consider_short_mate(best_score), NodeType::PV)
+    } else { // no move reached alpha, the score is an upper bound
+        (consider_short_mate(best_score), NodeType::ALL)
     }
 
 }
@@ -269,7 +342,7 @@ fn search_speed(b: &mut test::Bencher) {
 
     let fen = "3q1rk1/5ppp/2n2n2/p1pNb3/3pP3/3P3N/PPbB2PP/R3KB1R b KQ - 1 16";
 
-    let mut engine = Engine::new();
+    let mut engine = Engine::default();
     engine.load_fen(fen).unwrap();
 
     b.iter(|| {
@@ -278,4 +351,107 @@ fn search_speed(b: &mut test::Bencher) {
             engine.alpha_beta_search(i, search_data);
         }
     });
-}*/
\ No newline at end of file
+}*/
+#[test]
+fn test_fail_soft_bounds() {
+    use std::str::FromStr;
+
+    let search = |fen: &str, alpha: i32, beta: i32| {
+        let board = Board::from_str(fen).unwrap();
+        let mut path = SearchPath::new(&board, 0);
+
+        alpha_beta(board, alpha, beta, 3, Arc::new(SearchData::new(1)), &mut path, &mut Vec::new())
+    };
+
+    let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+    let (score, node_type) = search(fen, -INFINITE_SCORE, INFINITE_SCORE);
+    assert_eq!(node_type, NodeType::PV);
+
+    // a window above the score fails low and returns an upper bound
+    let (upper_bound, node_type) = search(fen, score + 50, score + 100);
+    assert_eq!(node_type, NodeType::ALL);
+    assert!(upper_bound <= score + 50 && upper_bound >= score);
+
+    // a window below the score fails high and returns a lower bound
+    let (lower_bound, node_type) = search(fen, score - 100, score - 50);
+    assert_eq!(node_type, NodeType::CUT);
+    assert!(lower_bound >= score - 50 && lower_bound <= score);
+
+    // the mirrored position has the same score for the side to move
+    let (mirrored, _) = search("rnbqkb1r/pppp1ppp/5n2/4p3/4P3/2N5/PPPP1PPP/R1BQKBNR b KQkq - 2 3", -INFINITE_SCORE, INFINITE_SCORE);
+    assert_eq!(mirrored, score);
+}
+
+#[test]
+fn test_principal_variation_search() {
+    use std::str::FromStr;
+    use crate::search::{ReductionTable, SearchFeatures};
+
+    // late move reductions and null moves are only used by the null window searches, they are turned off to get the same score
+    let search = |fen: &str, principal_variation_search: bool| {
+        let board = Board::from_str(fen).unwrap();
+        let mut path = SearchPath::new(&board, 0);
+
+        let mut search_data = SearchData::new(16);
+        search_data.features = SearchFeatures { null_move_pruning: false, principal_variation_search };
+        search_data.reductions = Arc::new(ReductionTable::new(-100.0, 1.0));
+        let search_data = Arc::new(search_data);
+
+        let (score, _) = alpha_beta(board, -INFINITE_SCORE, INFINITE_SCORE, 5, search_data.clone(), &mut path, &mut Vec::new());
+
+        (score, search_data.nodes())
+    };
+
+    for fen in ["r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3", "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1"] {
+        let (full_window, full_window_nodes) = search(fen, false);
+        let (principal_variation, principal_variation_nodes) = search(fen, true);
+
+        assert_eq!(principal_variation, full_window, "{fen}");
+        assert!(principal_variation_nodes < full_window_nodes, "{fen}: {principal_variation_nodes} >= {full_window_nodes}");
+    }
+}
+
+#[test]
+fn test_null_move_pruning() {
+    use std::str::FromStr;
+    use crate::search::SearchFeatures;
+
+    let middle_game = Board::from_str("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+    let path = SearchPath::new(&middle_game, 0);
+    assert!(allows_null_move(&middle_game, 3, &path));
+    assert!(!allows_null_move(&middle_game, 2, &path));
+
+    // in check, with only pawns or a single minor piece the side to move does not pass
+    for fen in ["rnbqkbnr/ppp2ppp/3p4/1B2p3/4P3/8/PPPP1PPP/RNBQK1NR b KQkq - 1 3", "8/5pk1/6p1/8/8/6P1/5PK1/8 w - - 0 1", "8/5pk1/6p1/8/8/6P1/5PK1/5N2 w - - 0 1"] {
+        let board = Board::from_str(fen).unwrap();
+        assert!(!allows_null_move(&board, 6, &SearchPath::new(&board, 0)), "{fen}");
+    }
+
+    // no second pass in a row
+    let mut path = SearchPath::new(&middle_game, 0);
+    path.push_null_move(&middle_game.null_move().unwrap());
+    assert!(!allows_null_move(&middle_game, 6, &path));
+
+    let search = |fen: &str, null_move_pruning: bool| {
+        let board = Board::from_str(fen).unwrap();
+        let mut path = SearchPath::new(&board, 0);
+
+        let mut search_data = SearchData::new(16);
+        search_data.features = SearchFeatures { null_move_pruning, ..SearchFeatures::default() };
+        let search_data = Arc::new(search_data);
+
+        let (score, _) = alpha_beta(board, -INFINITE_SCORE, INFINITE_SCORE, 6, search_data.clone(), &mut path, &mut Vec::new());
+
+        (score, search_data.nodes())
+    };
+
+    // a pawn ending is searched exactly like without null moves
+    let pawn_ending = "8/5pk1/6p1/8/8/6P1/5PK1/8 w - - 0 1";
+    assert_eq!(search(pawn_ending, true), search(pawn_ending, false));
+
+    // elsewhere null moves save nodes
+    let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+    let (_, with_null_moves) = search(fen, true);
+    let (_, without_null_moves) = search(fen, false);
+    assert!(with_null_moves < without_null_moves, "{with_null_moves} >= {without_null_moves}");
+}
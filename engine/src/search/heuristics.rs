@@ -0,0 +1,185 @@
+use std::sync::atomic::{AtomicI32, AtomicU32, Ordering};
+use chess::{ChessMove, Color, Piece, ALL_SQUARES};
+
+const MAX_PLY: usize = u8::MAX as usize + 1;
+const KILLER_SLOTS: usize = 2;
+
+// history scores stay between -MAX_HISTORY and MAX_HISTORY
+const MAX_HISTORY: i32 = 16_384;
+
+// the empty slot, no move goes from a1 to a1
+const NO_MOVE: u32 = 0;
+
+fn encode(joice: ChessMove) -> u32 {
+    let promotion = match joice.get_promotion() {
+        None => 0,
+        Some(Piece::Knight) => 1,
+        Some(Piece::Bishop) => 2,
+        Some(Piece::Rook) => 3,
+        Some(_) => 4,
+    };
+
+    joice.get_source().to_index() as u32 | (joice.get_dest().to_index() as u32) << 6 | promotion << 12
+}
+
+fn decode(value: u32) -> Option<ChessMove> {
+    if value == NO_MOVE {
+        return None;
+    }
+
+    let promotion = match value >> 12 {
+        0 => None,
+        1 => Some(Piece::Knight),
+        2 => Some(Piece::Bishop),
+        3 => Some(Piece::Rook),
+        _ => Some(Piece::Queen),
+    };
+
+    Some(ChessMove::new(ALL_SQUARES[(value & 63) as usize], ALL_SQUARES[(value >> 6 & 63) as usize], promotion))
+}
+
+/// Move ordering knowledge about quiet moves, learned from beta cutoffs.
+/// All search threads share the tables. Updates are atomic, but not synchronized
+/// with each other, a lost update only makes the move ordering slightly worse.
+pub(crate) struct MoveHeuristics {
+    // quiet moves which caused a cutoff at the same ply in another line
+    killers: [[AtomicU32; KILLER_SLOTS]; MAX_PLY],
+    // butterfly history, indexed by the side to move, the source and the destination
+    history: [[[AtomicI32; 64]; 64]; 2],
+    // the refutation of a move, indexed by its source and destination
+    counter_moves: [[AtomicU32; 64]; 64],
+}
+
+impl Default for MoveHeuristics {
+    fn default() -> Self {
+        MoveHeuristics {
+            killers: std::array::from_fn(|_| std::array::from_fn(|_| AtomicU32::new(NO_MOVE))),
+            history: std::array::from_fn(|_| std::array::from_fn(|_| std::array::from_fn(|_| AtomicI32::new(0)))),
+            counter_moves: std::array::from_fn(|_| std::array::from_fn(|_| AtomicU32::new(NO_MOVE))),
+        }
+    }
+}
+
+impl MoveHeuristics {
+    // killers only apply to the current search, the history is kept with less weight
+    pub fn new_search(&self) {
+        for slots in &self.killers {
+            for slot in slots {
+                slot.store(NO_MOVE, Ordering::Relaxed);
+            }
+        }
+
+        for entry in self.history.iter().flatten().flatten() {
+            entry.store(entry.load(Ordering::Relaxed) / 2, Ordering::Relaxed);
+        }
+    }
+
+    // nothing learned in the previous game is kept
+    pub fn clear(&self) {
+        for slot in self.killers.iter().flatten().chain(self.counter_moves.iter().flatten()) {
+            slot.store(NO_MOVE, Ordering::Relaxed);
+        }
+
+        for entry in self.history.iter().flatten().flatten() {
+            entry.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub fn is_killer(&self, ply: u8, joice: ChessMove) -> bool {
+        self.killers[ply as usize].iter().any(|slot| slot.load(Ordering::Relaxed) == encode(joice))
+    }
+
+    // the first killer is the most recent one
+    pub fn killers(&self, ply: u8) -> [Option<ChessMove>; KILLER_SLOTS] {
+        std::array::from_fn(|index| decode(self.killers[ply as usize][index].load(Ordering::Relaxed)))
+    }
+
+    pub fn history(&self, color: Color, joice: ChessMove) -> i32 {
+        self.history[color.to_index()][joice.get_source().to_index()][joice.get_dest().to_index()].load(Ordering::Relaxed)
+    }
+
+    pub fn counter_move(&self, previous: Option<ChessMove>) -> Option<ChessMove> {
+        let previous = previous?;
+        decode(self.counter_moves[previous.get_source().to_index()][previous.get_dest().to_index()].load(Ordering::Relaxed))
+    }
+
+    // the quiet move caused a beta cutoff, the quiet moves searched before it did not
+    pub fn update(&self, color: Color, ply: u8, depth_left: u8, previous: Option<ChessMove>, joice: ChessMove, failed: &[ChessMove]) {
+        let killers = &self.killers[ply as usize];
+        if killers[0].load(Ordering::Relaxed) != encode(joice) {
+            killers[1].store(killers[0].load(Ordering::Relaxed), Ordering::Relaxed);
+            killers[0].store(encode(joice), Ordering::Relaxed);
+        }
+
+        if let Some(previous) = previous {
+            self.counter_moves[previous.get_source().to_index()][previous.get_dest().to_index()].store(encode(joice), Ordering::Relaxed);
+        }
+
+        let bonus = (depth_left as i32 * depth_left as i32).min(MAX_HISTORY);
+
+        self.add_history(color, joice, bonus);
+        for &failed in failed {
+            self.add_history(color, failed, -bonus);
+        }
+    }
+
+    // frequent updates push the score less towards the limits
+    fn add_history(&self, color: Color, joice: ChessMove, bonus: i32) {
+        let entry = &self.history[color.to_index()][joice.get_source().to_index()][joice.get_dest().to_index()];
+        let score = entry.load(Ordering::Relaxed);
+
+        entry.store(score + bonus - score * bonus.abs() / MAX_HISTORY, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_encode_move() {
+    use std::str::FromStr;
+
+    for joice in ["e2e4", "a1a2", "h7h8q", "b2a1n", "g7g8r", "c2c1b"] {
+        let joice = ChessMove::from_str(joice).unwrap();
+        assert_eq!(decode(encode(joice)), Some(joice));
+    }
+
+    assert_eq!(decode(NO_MOVE), None);
+}
+
+#[test]
+fn test_move_heuristics() {
+    use std::str::FromStr;
+
+    let heuristics = MoveHeuristics::default();
+
+    let e2e4 = ChessMove::from_str("e2e4").unwrap();
+    let g1f3 = ChessMove::from_str("g1f3").unwrap();
+    let d2d4 = ChessMove::from_str("d2d4").unwrap();
+    let e7e5 = ChessMove::from_str("e7e5").unwrap();
+
+    heuristics.update(Color::White, 3, 4, Some(e7e5), g1f3, &[d2d4]);
+    heuristics.update(Color::White, 3, 4, None, e2e4, &[]);
+
+    assert_eq!(heuristics.killers(3), [Some(e2e4), Some(g1f3)]);
+    assert!(heuristics.is_killer(3, g1f3));
+    assert!(!heuristics.is_killer(4, g1f3));
+
+    assert_eq!(heuristics.counter_move(Some(e7e5)), Some(g1f3));
+    assert_eq!(heuristics.counter_move(None), None);
+
+    assert!(heuristics.history(Color::White, g1f3) > 0);
+    assert!(heuristics.history(Color::White, d2d4) < 0);
+    assert_eq!(heuristics.history(Color::Black, g1f3), 0);
+
+    // the same killer is not stored twice
+    heuristics.update(Color::White, 3, 4, None, e2e4, &[]);
+    assert_eq!(heuristics.killers(3), [Some(e2e4), Some(g1f3)]);
+
+    // the history is bounded
+    for _ in 0..1000 {
+        heuristics.update(Color::White, 3, 100, None, e2e4, &[]);
+    }
+    assert!(heuristics.history(Color::White, e2e4) <= MAX_HISTORY);
+
+    heuristics.new_search();
+    assert_eq!(heuristics.killers(3), [None, None]);
+    assert!(heuristics.history(Color::White, e2e4) <= MAX_HISTORY / 2);
+}
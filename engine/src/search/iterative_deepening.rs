@@ -1,77 +1,208 @@
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::Duration;
-use chess::ChessMove;
-use crate::Engine;
-use crate::evaluation::MATE_SCORE;
-use crate::search::{CURRENT_SEARCH_DEPTH, SearchData, STOP_THREADS};
+use std::time::{Duration, Instant};
+use chess::{Board, ChessMove, Color, MoveGen};
+use crate::{Engine, EngineError};
+use crate::search::{PvLine, Score, SearchLimits, SearchObserver, SearchPath, SearchResult, SearchStats, StopHandle};
+
+// Stop the search if time if over. While pondering the clock only starts with the ponder hit.
+fn timer(timeout: Duration, stop: StopHandle, finished: Arc<AtomicBool>) {
+    while stop.is_pondering() {
+        if finished.load(Ordering::SeqCst) { return }
+        // the ponder hit unparks the timer, but it may happen before the timer has been registered
+        std::thread::park_timeout(Duration::from_millis(10));
+    }
+
+    let deadline = stop.clock_start().unwrap_or_else(Instant::now) + timeout;
+
+    loop {
+        if finished.load(Ordering::SeqCst) { return }
+
+        let now = Instant::now();
+        if now >= deadline { break }
+
+        std::thread::park_timeout(deadline - now);
+    }
+
+    log::info!("Stop Searching.");
+
+    stop.stop();
+}
 
 impl Engine {
-    pub fn iterative_deepening(&self, search_data: Arc<SearchData>, timeout: Duration) -> ChessMove {
+    /// Search the current position until one of the limits is reached.
+    /// Closures can be used as observer, they are called for every completed depth.
+    pub fn iterative_deepening(&self, limits: &SearchLimits, observer: &impl SearchObserver) -> Result<SearchResult, EngineError> {
+        self.search_position(&self.game.current_position(), &self.game_path, self.game_ply(), limits, observer)
+    }
 
-        let soft_stop: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
-        let soft_stop_copy = soft_stop.clone();
+    /// Search the position after the expected reply of the opponent, while the opponent is thinking.
+    /// The game is not changed. The time limit starts with `StopHandle::ponder_hit`,
+    /// if the opponent plays another move the search should be stopped and its result discarded.
+    pub fn ponder(&self, ponder_move: ChessMove, limits: &SearchLimits, observer: &impl SearchObserver) -> Result<SearchResult, EngineError> {
+        if !self.game.current_position().legal(ponder_move) {
+            return Err(EngineError::IllegalMove(ponder_move.to_string()));
+        }
 
-        STOP_THREADS.store(false, Ordering::SeqCst);
-        CURRENT_SEARCH_DEPTH.store(0, Ordering::Relaxed);
+        let current_position = self.game.current_position();
+        let position = current_position.make_move_new(ponder_move);
 
-        // Stop the search if time if over
-        let handle = std::thread::spawn(move || {
-            std::thread::park_timeout(timeout);
+        let mut path = self.game_path.clone();
+        path.push(&current_position, ponder_move, &position);
 
-            soft_stop_copy.store(true, Ordering::SeqCst);
+        self.search_position(&position, &path, self.game_ply() + 1, &SearchLimits { ponder: true, ..limits.clone() }, observer)
+    }
 
-            log::info!("Stop Searching.");
+    // ply is the number of half moves played in the game up to the root
+    fn search_position(&self, root: &Board, path: &SearchPath, ply: u16, limits: &SearchLimits, observer: &dyn SearchObserver) -> Result<SearchResult, EngineError> {
 
-            // if at least depth 6 is searched hard stop
-            if CURRENT_SEARCH_DEPTH.load(Ordering::Relaxed) > 1 {
-                STOP_THREADS.store(true, Ordering::SeqCst);
-            }
+        if MoveGen::new_legal(root).len() == 0 {
+            return Err(EngineError::NoLegalMoves);
+        }
+
+        let search_data = self.search_data.clone();
+
+        let stop = search_data.stop.clone();
+        stop.start_search(limits.ponder);
+
+        // the engine is the side to move at the root, even while pondering
+        let contempt = self.config.contempt();
+        let draw_score = if root.side_to_move() == Color::White { -contempt } else { contempt };
+        search_data.draw_score.store(draw_score, Ordering::Relaxed);
 
+        search_data.nodes.store(0, Ordering::Relaxed);
+        search_data.stats.reset();
+        search_data.heuristics.new_search();
+        search_data.node_limit.store(limits.nodes.unwrap_or(u64::MAX), Ordering::Relaxed);
 
+        let finished = Arc::new(AtomicBool::new(false));
+
+        let mut time_manager = limits.time_manager(ply, self.config.move_overhead());
+
+        let handle = time_manager.as_ref().map(|time_manager| {
+            let timeout = time_manager.hard_limit();
+            let stop = stop.clone();
+            let finished = finished.clone();
+
+            std::thread::spawn(move || timer(timeout, stop, finished))
         });
 
-        let start_time = std::time::Instant::now();
+        stop.set_timer(handle.as_ref().map(|handle| handle.thread().clone()));
+
+        let start_time = Instant::now();
 
-        let mut best_move: Option<ChessMove> = None;
-        for current_depth in (1..255).step_by(1) {
-            CURRENT_SEARCH_DEPTH.store(current_depth, Ordering::Relaxed);
+        // plies are counted from the root of this search
+        let mut path = path.clone();
+        path.set_root();
 
-            let (new_best_move, score) = self.alpha_beta_search(current_depth, search_data.clone());
+        let mut result: Option<SearchResult> = None;
+        let mut previous_root_score: Option<i32> = None;
+        // nodes of the previous depth and of all depths before it
+        let (mut previous_depth_nodes, mut searched_nodes) = (0, 0);
+        for current_depth in 1..=limits.max_depth() {
+            stop.set_depth(current_depth);
+
+            let root_moves = self.alpha_beta_search(root, &path, current_depth, search_data.clone(), observer);
+            let new_best_move = root_moves.first().map(|root_move| root_move.pv[0]);
 
             if let Some(joice) = new_best_move {
-                assert!(self.game.current_position().legal(joice));
+                assert!(root.legal(joice));
             }
 
             // The current layer has been stopped before the calculation finished
-            let hard_stop = STOP_THREADS.load(Ordering::SeqCst);
+            let hard_stop = stop.is_hard_stopped();
 
             // Time is up, but calculation has not been representative
-            let soft_stop = soft_stop.load(Ordering::SeqCst);
+            let soft_stop = stop.is_soft_stopped();
 
             if hard_stop || new_best_move.is_none() {
                 break;
             }
 
-            log::info!("Best move of depth {current_depth} is {} with score: {score}", new_best_move.unwrap().to_string());
+            let score = root_moves[0].score;
+
+            log::info!("Best move of depth {current_depth} is {} with score: {score}", new_best_move.unwrap());
+
+            let lines = root_moves.iter()
+                .take(self.multi_pv)
+                .map(|root_move| PvLine {
+                    score: Score::relative(root_move.score),
+                    pv: root_move.pv.clone(),
+                })
+                .collect::<Vec<PvLine>>();
+
+            let nodes = search_data.nodes();
+            let depth_nodes = nodes - searched_nodes;
+
+            let stats = SearchStats {
+                branching_factor: if previous_depth_nodes > 0 { depth_nodes as f64 / previous_depth_nodes as f64 } else { 0.0 },
+                ..search_data.stats.snapshot()
+            };
+
+            (previous_depth_nodes, searched_nodes) = (depth_nodes, nodes);
+
+            let iteration = SearchResult {
+                best_move: new_best_move.unwrap(),
+                score: lines[0].score,
+                depth: current_depth,
+                pv: lines[0].pv.clone(),
+                lines,
+                nodes,
+                elapsed: start_time.elapsed(),
+                stats,
+                from_book: false,
+            };
+
+            // keep on pondering even if a mate has been found
+            let satisfied = limits.is_satisfied_by(iteration.score) && !stop.is_pondering();
+
+            let best_move_changed = result.as_ref().map(|previous| previous.best_move) != Some(iteration.best_move);
+            if best_move_changed {
+                observer.new_best_move(current_depth, iteration.best_move, iteration.score);
+            }
 
-            best_move = new_best_move;
+            // the clock of a ponder search starts with the ponder hit
+            let time_left = match (&mut time_manager, stop.clock_start()) {
+                (Some(time_manager), Some(clock_start)) => {
+                    // scores of the root moves are relative to the engine
+                    let score_change = previous_root_score.map(|previous| root_moves[0].score - previous).unwrap_or(0);
+                    let margin = root_moves.get(1).map(|second| root_moves[0].score - second.score);
 
-            if score.abs() > MATE_SCORE - 1_000 {
-                break;
-            }
+                    // the first depth has nothing to compare with
+                    time_manager.continue_search(clock_start.elapsed(), best_move_changed && result.is_some(), score_change, margin)
+                }
+                _ => true
+            };
+            previous_root_score = Some(root_moves[0].score);
 
-            if soft_stop {
+            observer.iteration_completed(&iteration);
+
+            result = Some(iteration);
+
+            if satisfied || soft_stop || !time_left {
                 break;
             }
-
-            log::error!("Time elaps: {}", start_time.elapsed().as_micros());
         }
 
         // necessary if search ends early (e.g. mate was found)
-        handle.thread().unpark();
+        finished.store(true, Ordering::SeqCst);
+        stop.set_timer(None);
+
+        if let Some(handle) = handle {
+            handle.thread().unpark();
+            handle.join().unwrap();
+        }
+
+        let mut result = result.expect("Could not find a good move...");
+
+        // account for the work and time of an interrupted depth
+        result.nodes = search_data.nodes();
+        result.elapsed = start_time.elapsed();
+        result.stats = SearchStats { branching_factor: result.stats.branching_factor, ..search_data.stats.snapshot() };
+
+        observer.search_finished(&result);
 
-        best_move.expect("Could not find a good move...")
+        Ok(result)
 
     }
-}
\ No newline at end of file
+}
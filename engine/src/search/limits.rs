@@ -0,0 +1,115 @@
+use std::time::Duration;
+use crate::search::{Clock, Score, TimeManager};
+
+const MAX_DEPTH: u8 = 254;
+
+/// Conditions which end a search, the first one reached stops it.
+/// Without any limit the search runs until it is stopped from outside.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub move_time: Option<Duration>,
+    /// Time control of the engine, ignored if a move time is given
+    pub clock: Option<Clock>,
+    /// Search until stopped, even if the move time is over or a mate has been found
+    pub infinite: bool,
+    /// Stop as soon as a mate in this many moves has been found
+    pub mate: Option<u8>,
+    /// Search during the time of the opponent. No limit is used until the ponder hit.
+    pub ponder: bool,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u8) -> SearchLimits {
+        SearchLimits { depth: Some(depth), ..Default::default() }
+    }
+
+    pub fn nodes(nodes: u64) -> SearchLimits {
+        SearchLimits { nodes: Some(nodes), ..Default::default() }
+    }
+
+    pub fn move_time(move_time: Duration) -> SearchLimits {
+        SearchLimits { move_time: Some(move_time), ..Default::default() }
+    }
+
+    pub fn clock(clock: Clock) -> SearchLimits {
+        SearchLimits { clock: Some(clock), ..Default::default() }
+    }
+
+    pub fn infinite() -> SearchLimits {
+        SearchLimits { infinite: true, ..Default::default() }
+    }
+
+    pub fn mate(moves: u8) -> SearchLimits {
+        SearchLimits { mate: Some(moves), ..Default::default() }
+    }
+
+    pub(crate) fn time_manager(&self, ply: u16, move_overhead: Duration) -> Option<TimeManager> {
+        if self.infinite {
+            return None;
+        }
+
+        match (self.move_time, &self.clock) {
+            (Some(move_time), _) => Some(TimeManager::fixed(move_time)),
+            (None, Some(clock)) => Some(TimeManager::new(clock, ply, move_overhead)),
+            (None, None) => None
+        }
+    }
+
+    pub(crate) fn max_depth(&self) -> u8 {
+        // a mate in n moves is found after 2n - 1 plies
+        let mate_depth = self.mate.map(|moves| (2 * moves as u16).saturating_sub(1).min(MAX_DEPTH as u16) as u8);
+
+        self.depth.into_iter()
+            .chain(mate_depth)
+            .min()
+            .unwrap_or(MAX_DEPTH)
+            .clamp(1, MAX_DEPTH)
+    }
+
+    // check if the result of a completed depth is good enough to end the search
+    pub(crate) fn is_satisfied_by(&self, score: Score) -> bool {
+        match (score, self.mate) {
+            (Score::Mate(moves), Some(limit)) => moves > 0 && moves <= limit as i32,
+            // searching deeper will not change a forced mate
+            (Score::Mate(_), None) => !self.infinite,
+            _ => false
+        }
+    }
+}
+
+#[test]
+fn test_max_depth() {
+    assert_eq!(SearchLimits::default().max_depth(), MAX_DEPTH);
+    assert_eq!(SearchLimits::depth(5).max_depth(), 5);
+    assert_eq!(SearchLimits::depth(0).max_depth(), 1);
+    assert_eq!(SearchLimits::mate(3).max_depth(), 5);
+    assert_eq!(SearchLimits { depth: Some(3), mate: Some(4), ..Default::default() }.max_depth(), 3);
+}
+
+#[test]
+fn test_is_satisfied_by() {
+    assert!(SearchLimits::default().is_satisfied_by(Score::Mate(2)));
+    assert!(SearchLimits::default().is_satisfied_by(Score::Mate(-2)));
+    assert!(!SearchLimits::infinite().is_satisfied_by(Score::Mate(2)));
+    assert!(!SearchLimits::default().is_satisfied_by(Score::Centipawns(500)));
+
+    assert!(SearchLimits::mate(2).is_satisfied_by(Score::Mate(2)));
+    assert!(!SearchLimits::mate(2).is_satisfied_by(Score::Mate(3)));
+    assert!(!SearchLimits::mate(2).is_satisfied_by(Score::Mate(-1)));
+}
+
+#[test]
+fn test_time_manager() {
+    let second = Duration::from_secs(1);
+    let clock = Clock { time_left: Duration::from_secs(60), increment: Duration::ZERO, moves_to_go: None };
+
+    let hard_limit = |limits: SearchLimits| limits.time_manager(0, Duration::ZERO).map(|time_manager| time_manager.hard_limit());
+
+    assert_eq!(hard_limit(SearchLimits::move_time(second)), Some(second));
+    assert_eq!(hard_limit(SearchLimits { move_time: Some(second), clock: Some(clock), ..Default::default() }), Some(second));
+    assert_eq!(hard_limit(SearchLimits { infinite: true, move_time: Some(second), ..Default::default() }), None);
+    assert_eq!(hard_limit(SearchLimits::depth(3)), None);
+    assert!(hard_limit(SearchLimits::clock(clock)).unwrap() < Duration::from_secs(60));
+}
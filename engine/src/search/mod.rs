@@ -1,19 +1,39 @@
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, AtomicU8};
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use chashmap::CHashMap;
-use chess::{Board, ChessMove};
+use chess::{ChessMove, Color};
+use crate::evaluation::DRAW_SCORE;
 use crate::transposition_table::entry::Entry;
 use crate::transposition_table::table::TranspositionTable;
+use crate::search::heuristics::MoveHeuristics;
+use crate::search::stats::SearchCounters;
 
 mod move_order;
 mod alpha_beta;
+mod heuristics;
 mod iterative_deepening;
+mod limits;
+mod observer;
+mod path;
 mod quiesce_search;
-
-static STOP_THREADS: AtomicBool = AtomicBool::new(false);
-pub static CURRENT_SEARCH_DEPTH: AtomicU8 = AtomicU8::new(0);
-
+mod pv;
+mod reductions;
+mod result;
+mod stats;
+mod stop;
+mod time_manager;
+
+pub use limits::SearchLimits;
+pub use observer::{SearchObserver, WindowFail};
+pub(crate) use path::SearchPath;
+pub use reductions::ReductionTable;
+pub use result::{PvLine, Score, SearchResult};
+pub use stats::SearchStats;
+pub use stop::StopHandle;
+pub use time_manager::{Clock, TimeManager};
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum NodeType {
     PV, // Exact value
@@ -21,48 +41,89 @@ pub enum NodeType {
     ALL // Upper bound
 }
 
+// parts of the search which can be turned off, to compare them with the plain alpha beta search
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct SearchFeatures {
+    pub null_move_pruning: bool,
+    pub principal_variation_search: bool,
+}
+
+impl Default for SearchFeatures {
+    fn default() -> Self {
+        SearchFeatures { null_move_pruning: true, principal_variation_search: true }
+    }
+}
+
 pub struct SearchData {
     pub transposition_table: TranspositionTable,
     pub previous_score: Mutex<HashMap<u64, i32>>,
-    positions_visited: RwLock<HashMap<u64, u8>>,
-    best_moves: CHashMap<u64, ChessMove>
+    best_moves: CHashMap<u64, ChessMove>,
+    nodes: AtomicU64,
+    node_limit: AtomicU64,
+    stats: SearchCounters,
+    heuristics: MoveHeuristics,
+    // white relative score of a draw, depends on the contempt and the side of the engine
+    draw_score: AtomicI32,
+    pub reductions: Arc<ReductionTable>,
+    features: SearchFeatures,
+    pub stop: StopHandle
 }
 
 impl SearchData {
-    pub fn new() -> SearchData {
+    pub fn new(hash_mb: usize) -> SearchData {
         SearchData {
-            transposition_table: TranspositionTable::new(),
+            transposition_table: TranspositionTable::new(hash_mb),
             previous_score: Mutex::new(HashMap::new()),
-            positions_visited: RwLock::new(HashMap::new()),
-            best_moves: CHashMap::new()
+            best_moves: CHashMap::new(),
+            nodes: AtomicU64::new(0),
+            node_limit: AtomicU64::new(u64::MAX),
+            stats: SearchCounters::default(),
+            heuristics: MoveHeuristics::default(),
+            draw_score: AtomicI32::new(DRAW_SCORE),
+            reductions: Arc::new(ReductionTable::default()),
+            features: SearchFeatures::default(),
+            stop: StopHandle::new()
         }
     }
 
-    pub fn position_visited_twice(&self, position: &Board) -> bool {
-        if let Some(visited) = self.positions_visited.read().unwrap().get(&position.get_hash()) {
-            return visited >= &2
-        }
+    // forget everything learned about earlier positions, the table keeps its size
+    pub fn clear(&self) {
+        self.transposition_table.clear();
+        self.previous_score.lock().unwrap().clear();
+        self.best_moves.clear();
+        self.heuristics.clear();
+    }
 
-        false
+    // score of a draw relative to the side to move
+    pub fn draw_score(&self, side_to_move: Color) -> i32 {
+        let draw_score = self.draw_score.load(Ordering::Relaxed);
+
+        if side_to_move == Color::White { draw_score } else { -draw_score }
     }
 
-    pub fn visit_position(&self, position: &Board) {
-        let hash = position.get_hash();
-        let mut visited_lock = self.positions_visited.write().unwrap();
+    pub fn nodes(&self) -> u64 {
+        self.nodes.load(Ordering::Relaxed)
+    }
 
-        let mut visited = 0;
+    // count a node of the quiescence search, it is part of the total nodes
+    fn visit_quiescence_node(&self, ply: u8) {
+        self.stats.qnode();
+        self.visit_node(ply);
+    }
 
-        if let Some(&v) = visited_lock.get(&hash) {
-            visited = v;
-        }
+    // count a visited node and stop the search if the node budget is used up
+    fn visit_node(&self, ply: u8) {
+        self.stats.reach_ply(ply);
 
-        visited += 1;
+        let nodes = self.nodes.fetch_add(1, Ordering::Relaxed) + 1;
 
-        visited_lock.insert(hash, visited);
+        if nodes >= self.node_limit.load(Ordering::Relaxed) {
+            self.stop.stop();
+        }
     }
 
     pub fn get_or_calculate<F>(this: Arc<SearchData>, hash: u64, alpha: i32, beta: i32, depth: u8, calculate: F) -> i32
-        where F: Fn(Arc<SearchData>) -> (i32, NodeType) {
+        where F: FnOnce(Arc<SearchData>) -> (i32, NodeType) {
 
         let entry = this.transposition_table.get(hash);
 
@@ -70,17 +131,23 @@ impl SearchData {
         'stored_value: {
             let entry = entry.read().unwrap();
 
+            let hit = matches!(*entry, Entry::Contains { hash: _hash, .. } if _hash == hash);
+            this.stats.tt_probe(hit);
+
             if let Entry::Contains { depth: _depth, hash: _hash, score, node_type, .. } = *entry {
 
                 // check if the stored bound is good enough
                 match node_type {
-                    NodeType::CUT => if beta > score { break 'stored_value } /* bad score */
-                    NodeType::ALL => if alpha < score { break 'stored_value } /* bad score */
-                    NodeType::PV => { if !(alpha <= score && score <= beta) { break 'stored_value } } // TODO think about this
+                    // a lower bound is only good enough if it fails high
+                    NodeType::CUT => if score < beta { break 'stored_value }
+                    // an upper bound is only good enough if it fails low
+                    NodeType::ALL => if score > alpha { break 'stored_value }
+                    NodeType::PV => {}
                 }
 
                 // value has been searched deep enough
-                if hash == _hash && _depth >= depth {
+                if hit && _depth >= depth {
+                    this.stats.tt_cutoff();
                     return score
                 }
             }
@@ -89,6 +156,11 @@ impl SearchData {
 
         let (score, node_type) = calculate(this.clone());
 
+        // the score of an interrupted search is not reliable
+        if this.stop.is_hard_stopped() {
+            return score;
+        }
+
         // push score to transposition table
         {
             let mut entry = entry.write().unwrap();
@@ -13,15 +13,35 @@ fn piece_type(p: &Piece) -> u8 {
     }
 }
 
-// put captures before other moves
-pub fn get_move_order(board: &Board, search_data: Arc<SearchData>) -> Vec<ChessMove> {
+// en passant is the only capture to an empty square
+pub fn is_capture(board: &Board, joice: ChessMove) -> bool {
+    board.piece_on(joice.get_dest()).is_some()
+        || (board.piece_on(joice.get_source()) == Some(Piece::Pawn) && joice.get_source().get_file() != joice.get_dest().get_file())
+}
+
+// killers first, then the refutation of the previous move, then by history
+fn quiet_move_score(joice: ChessMove, killers: &[Option<ChessMove>], counter_move: Option<ChessMove>, history: i32) -> i32 {
+    if let Some(index) = killers.iter().position(|&killer| killer == Some(joice)) {
+        return i32::MAX - index as i32;
+    }
+
+    if counter_move == Some(joice) {
+        return i32::MAX - killers.len() as i32;
+    }
+
+    history
+}
+
+// put the best move of a previous search first, then captures, then quiet moves ordered by the move heuristics.
+// Ply and previous move select the killers and the counter move.
+pub fn get_move_order(board: &Board, search_data: Arc<SearchData>, ply: u8, previous: Option<ChessMove>) -> Vec<ChessMove> {
     let mut moves: Vec<ChessMove> = Vec::new();
 
     if let Some(pre_move) = search_data.best_moves.get(&board.get_hash()) {
         moves.push(*pre_move);
     }
 
-    let mut all_moves = MoveGen::new_legal(&board);
+    let mut all_moves = MoveGen::new_legal(board);
     let targets = board.color_combined(!board.side_to_move());
     all_moves.set_iterator_mask(*targets);
 
@@ -51,7 +71,16 @@ pub fn get_move_order(board: &Board, search_data: Arc<SearchData>) -> Vec<ChessM
     }
 
     all_moves.set_iterator_mask(!EMPTY);
-    let second_half = all_moves.collect::<Vec<ChessMove>>();
+    let mut second_half = all_moves.collect::<Vec<ChessMove>>();
+
+    let heuristics = &search_data.heuristics;
+    let killers = heuristics.killers(ply);
+    let counter_move = heuristics.counter_move(previous);
+
+    // the sort is stable, moves without any score keep the order of the move generator
+    second_half.sort_by_cached_key(|&joice| std::cmp::Reverse(
+        quiet_move_score(joice, &killers, counter_move, heuristics.history(board.side_to_move(), joice))
+    ));
 
     for joice in &second_half {
         if !moves.contains(joice) {
@@ -63,7 +92,7 @@ pub fn get_move_order(board: &Board, search_data: Arc<SearchData>) -> Vec<ChessM
 }
 
 pub fn get_move_order_captures(board: &Board) -> Vec<ChessMove> {
-    let mut iterable = MoveGen::new_legal(&board);
+    let mut iterable = MoveGen::new_legal(board);
 
     let targets = board.color_combined(!board.side_to_move());
     iterable.set_iterator_mask(*targets);
@@ -94,13 +123,13 @@ pub fn get_move_order_captures(board: &Board) -> Vec<ChessMove> {
 #[test]
 fn test_move_order() {
     use std::str::FromStr;
-    use chess::{ChessMove, ALL_SQUARES, MoveGen};
+    use chess::{ChessMove, MoveGen};
 
     let board = Board::from_str("rnbqkbnr/1p2p1pp/p4p2/8/3P4/4p1P1/PPP4P/RNBQKBNR b KQkq - 0 4").unwrap();
 
     let all_moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
 
-    let moves_in_order: Vec<ChessMove> = get_move_order(&board, Arc::new(SearchData::new()));
+    let moves_in_order: Vec<ChessMove> = get_move_order(&board, Arc::new(SearchData::new(1)), 0, None);
 
     // Check if both arrays are the same
     assert_eq!(all_moves.len(), moves_in_order.len());
@@ -114,16 +143,16 @@ fn test_move_order() {
 #[test]
 fn test_right_move_order() {
     use std::str::FromStr;
-    use chess::{ChessMove, ALL_SQUARES, MoveGen};
+    use chess::{ChessMove, MoveGen};
 
     let board = Board::from_str("rnbqkbnr/1p2p1pp/p4p2/8/3P4/4p1P1/PPP4P/RNBQKBNR b KQkq - 0 4").unwrap();
 
     let all_moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
 
-    let mut search_data = Arc::new(SearchData::new());
+    let search_data = Arc::new(SearchData::new(1));
     search_data.best_moves.insert(board.get_hash(), *all_moves.last().unwrap());
 
-    let moves_in_order: Vec<ChessMove> = get_move_order(&board, search_data);
+    let moves_in_order: Vec<ChessMove> = get_move_order(&board, search_data, 0, None);
 
     // Check if both arrays are the same
     assert_eq!(all_moves.len(), moves_in_order.len());
@@ -134,4 +163,71 @@ fn test_right_move_order() {
 
     // check if best move first approach works
     assert_eq!(all_moves.last(), moves_in_order.first())
-}
\ No newline at end of file
+}
+
+// perft through the move ordering, with a best move stored for every position
+#[cfg(test)]
+fn perft_move_order(board: &Board, depth: u8, search_data: &Arc<SearchData>) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    if let Some(joice) = MoveGen::new_legal(board).last() {
+        search_data.best_moves.insert(board.get_hash(), joice);
+    }
+
+    let moves = get_move_order(board, search_data.clone(), 0, None);
+
+    moves.iter()
+        .map(|joice| perft_move_order(&board.make_move_new(*joice), depth - 1, search_data))
+        .sum()
+}
+
+#[test]
+fn test_perft_move_order() {
+    use std::str::FromStr;
+    use crate::perft::perft;
+
+    let positions = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    ];
+
+    for fen in positions {
+        let board = Board::from_str(fen).unwrap();
+        let search_data = Arc::new(SearchData::new(1));
+
+        assert_eq!(perft_move_order(&board, 3, &search_data), perft(&board, 3), "{fen}");
+    }
+}
+
+#[test]
+fn test_quiet_move_order() {
+    use std::str::FromStr;
+    use chess::Color;
+
+    let board = Board::default();
+    let search_data = Arc::new(SearchData::new(1));
+
+    let e2e4 = ChessMove::from_str("e2e4").unwrap();
+    let g1f3 = ChessMove::from_str("g1f3").unwrap();
+    let b1c3 = ChessMove::from_str("b1c3").unwrap();
+    let d2d4 = ChessMove::from_str("d2d4").unwrap();
+    let e7e5 = ChessMove::from_str("e7e5").unwrap();
+
+    // b1c3 gets a history bonus, g1f3 becomes a killer at ply 2 and d2d4 the answer to e7e5
+    search_data.heuristics.update(Color::White, 5, 6, None, b1c3, &[]);
+    search_data.heuristics.update(Color::White, 2, 1, None, g1f3, &[]);
+    search_data.heuristics.update(Color::White, 7, 1, Some(e7e5), d2d4, &[]);
+
+    let moves = get_move_order(&board, search_data.clone(), 2, Some(e7e5));
+    assert_eq!(moves[..2], [g1f3, d2d4]);
+    assert!(moves.iter().position(|&joice| joice == b1c3) < moves.iter().position(|&joice| joice == e2e4));
+
+    // the killers of another ply do not count
+    let moves = get_move_order(&board, search_data, 3, None);
+    assert_eq!(moves[0], b1c3);
+    assert_eq!(moves.len(), 20);
+}
@@ -0,0 +1,38 @@
+use chess::ChessMove;
+use crate::search::{Score, SearchResult};
+
+/// Side of the aspiration window a root move fell out of, from the point of view of the side to move
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum WindowFail {
+    High,
+    Low,
+}
+
+/// Receives the progress of a running search, every event has an empty default implementation.
+/// The root moves are searched in parallel, so events may arrive from several threads at once.
+pub trait SearchObserver: Sync {
+    /// A depth has been searched completely
+    fn iteration_completed(&self, _result: &SearchResult) {}
+
+    /// The best move differs from the one of the previous depth
+    fn new_best_move(&self, _depth: u8, _joice: ChessMove, _score: Score) {}
+
+    /// A root move got its exact score
+    fn root_move_scored(&self, _depth: u8, _joice: ChessMove, _score: Score) {}
+
+    /// The score of a root move was outside of its aspiration window, it is searched again with a wider one
+    fn window_failed(&self, _depth: u8, _joice: ChessMove, _fail: WindowFail) {}
+
+    /// The search has ended, the result is the one returned to the caller
+    fn search_finished(&self, _result: &SearchResult) {}
+}
+
+// observer which ignores all events
+impl SearchObserver for () {}
+
+// closures are only interested in the completed depths
+impl<F: Fn(&SearchResult) + Sync> SearchObserver for F {
+    fn iteration_completed(&self, result: &SearchResult) {
+        self(result)
+    }
+}
@@ -0,0 +1,214 @@
+use chess::{Board, BoardStatus, ChessMove, Piece};
+
+// a position can repeat after four plies at the earliest
+const MIN_REPETITION_DISTANCE: usize = 4;
+const FIFTY_MOVES: u16 = 100;
+
+#[derive(Copy, Clone, Debug)]
+struct PathEntry {
+    hash: u64,
+    // plies since the last capture or pawn move
+    halfmove_clock: u16,
+    // the move which lead to the position, None at the start and after passing
+    joice: Option<ChessMove>,
+    // the position has been reached by passing
+    null_move: bool,
+}
+
+/// Positions of the game followed by the positions of the current search line, the current position last.
+/// Used to find repetitions and to apply the fifty move rule at any ply.
+#[derive(Clone, Debug)]
+pub struct SearchPath {
+    entries: Vec<PathEntry>,
+    // index of the root of the search
+    root: usize,
+}
+
+impl SearchPath {
+    pub fn new(root: &Board, halfmove_clock: u16) -> SearchPath {
+        SearchPath {
+            entries: vec![PathEntry { hash: root.get_hash(), halfmove_clock, joice: None, null_move: false }],
+            root: 0,
+        }
+    }
+
+    // the current position becomes the root of a search
+    pub fn set_root(&mut self) {
+        self.root = self.entries.len() - 1;
+    }
+
+    // distance of the current position to the root of the search
+    pub fn ply(&self) -> u8 {
+        (self.entries.len() - 1).saturating_sub(self.root).min(u8::MAX as usize) as u8
+    }
+
+    // board is the position before joice, child the one after it
+    pub fn push(&mut self, board: &Board, joice: ChessMove, child: &Board) {
+        let irreversible = board.piece_on(joice.get_source()) == Some(Piece::Pawn) || board.piece_on(joice.get_dest()).is_some();
+
+        let halfmove_clock = if irreversible { 0 } else { self.halfmove_clock().saturating_add(1) };
+
+        self.entries.push(PathEntry { hash: child.get_hash(), halfmove_clock, joice: Some(joice), null_move: false });
+    }
+
+    // Passing is no legal move, so positions before it can not be repeated after it.
+    // The clock is reset like after an irreversible move.
+    pub fn push_null_move(&mut self, child: &Board) {
+        self.entries.push(PathEntry { hash: child.get_hash(), halfmove_clock: 0, joice: None, null_move: true });
+    }
+
+    pub fn last_move(&self) -> Option<ChessMove> {
+        self.entries.last().and_then(|entry| entry.joice)
+    }
+
+    pub fn is_after_null_move(&self) -> bool {
+        self.entries.last().is_some_and(|entry| entry.null_move)
+    }
+
+    pub fn pop(&mut self) {
+        // the first position always stays on the path
+        if self.entries.len() > 1 {
+            self.entries.pop();
+        }
+    }
+
+    pub fn halfmove_clock(&self) -> u16 {
+        self.entries.last().map(|entry| entry.halfmove_clock).unwrap_or(0)
+    }
+
+    // indexes of earlier occurrences of the current position, only positions since the last irreversible move can repeat
+    fn earlier_occurrences(&self) -> impl Iterator<Item = usize> + '_ {
+        let last = self.entries.len() - 1;
+        let current = self.entries[last];
+
+        (0..last + 1).rev()
+            .take(current.halfmove_clock as usize + 1)
+            .skip(MIN_REPETITION_DISTANCE)
+            .step_by(2)
+            .filter(move |&index| self.entries[index].hash == current.hash)
+    }
+
+    // number of earlier occurrences of the current position
+    pub fn repetitions(&self) -> usize {
+        self.earlier_occurrences().count()
+    }
+
+    // The current position is a draw by repetition or the fifty move rule.
+    // Inside the search a single repetition is enough, as the side which could avoid it has no reason to repeat.
+    // Positions up to the root need two earlier occurrences, the opponent may still deviate after the first one.
+    pub fn is_draw(&self, board: &Board) -> bool {
+        let repeated = self.earlier_occurrences().any(|index| index > self.root) || self.repetitions() >= 2;
+
+        repeated || (self.halfmove_clock() >= FIFTY_MOVES && board.status() != BoardStatus::Checkmate)
+    }
+}
+
+#[test]
+fn test_repetition() {
+    use std::str::FromStr;
+
+    let knight_moves = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+    let play = |path: &mut SearchPath, board: &mut Board, moves: &[&str]| {
+        for joice in moves {
+            let joice = ChessMove::from_str(joice).unwrap();
+            let child = board.make_move_new(joice);
+
+            path.push(board, joice, &child);
+            *board = child;
+        }
+    };
+
+    let mut board = Board::default();
+    let mut path = SearchPath::new(&board, 0);
+
+    // returning to the root once is no draw, the root could have been reached from the start position before
+    play(&mut path, &mut board, &knight_moves);
+    assert_eq!(path.repetitions(), 1);
+    assert!(!path.is_draw(&board));
+
+    // a position after the root which repeats inside the search is
+    play(&mut path, &mut board, &knight_moves[..1]);
+    assert_eq!(path.repetitions(), 1);
+    assert!(path.is_draw(&board));
+
+    path.pop();
+    assert!(!path.is_draw(&board));
+
+    // the game has repeated the start position once before the search starts
+    let mut board = Board::default();
+    let mut path = SearchPath::new(&board, 0);
+    play(&mut path, &mut board, &knight_moves);
+    path.set_root();
+
+    // a single repetition of a game position is no draw, the opponent may still deviate
+    play(&mut path, &mut board, &knight_moves[..2]);
+    assert_eq!(path.repetitions(), 1);
+    assert!(!path.is_draw(&board));
+
+    // the third occurrence of the start position is
+    play(&mut path, &mut board, &knight_moves[2..]);
+    assert_eq!(path.repetitions(), 2);
+    assert!(path.is_draw(&board));
+
+    // a pawn move resets the clock, earlier positions can not repeat any more
+    let mut path = SearchPath::new(&board, 0);
+    let e2e4 = ChessMove::from_str("e2e4").unwrap();
+    let child = board.make_move_new(e2e4);
+    path.push(&board, e2e4, &child);
+    assert_eq!(path.halfmove_clock(), 0);
+}
+
+#[test]
+fn test_fifty_move_rule() {
+    use std::str::FromStr;
+
+    let board = Board::from_str("8/8/8/4k3/8/8/4K3/4R3 w - - 0 1").unwrap();
+    let joice = ChessMove::from_str("e1a1").unwrap();
+    let child = board.make_move_new(joice);
+
+    let mut path = SearchPath::new(&board, 98);
+    path.push(&board, joice, &child);
+    assert!(!path.is_draw(&child));
+
+    let back = ChessMove::from_str("e5d5").unwrap();
+    let next = child.make_move_new(back);
+    path.push(&child, back, &next);
+    assert_eq!(path.halfmove_clock(), 100);
+    assert!(path.is_draw(&next));
+
+    // checkmate takes precedence
+    let mate = Board::from_str("k7/8/1K6/8/8/8/8/7R w - - 99 80").unwrap();
+    let joice = ChessMove::from_str("h1h8").unwrap();
+    let child = mate.make_move_new(joice);
+
+    let mut path = SearchPath::new(&mate, 99);
+    path.push(&mate, joice, &child);
+    assert!(!path.is_draw(&child));
+}
+
+#[test]
+fn test_null_move() {
+    use std::str::FromStr;
+
+    let board = Board::default();
+    let mut path = SearchPath::new(&board, 0);
+
+    let g1f3 = ChessMove::from_str("g1f3").unwrap();
+    let child = board.make_move_new(g1f3);
+    path.push(&board, g1f3, &child);
+
+    let passed = child.null_move().unwrap();
+    path.push_null_move(&passed);
+    assert!(path.is_after_null_move());
+    assert_eq!(path.last_move(), None);
+    assert_eq!(path.ply(), 2);
+
+    // the knight returns, but the position before the null move does not count
+    let f3g1 = ChessMove::from_str("f3g1").unwrap();
+    let back = passed.make_move_new(f3g1);
+    path.push(&passed, f3g1, &back);
+    assert!(!path.is_after_null_move());
+    assert_eq!(path.last_move(), Some(f3g1));
+    assert_eq!(path.repetitions(), 0);
+}
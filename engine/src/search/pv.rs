@@ -0,0 +1,72 @@
+use std::collections::HashSet;
+use chess::{Board, ChessMove};
+use crate::search::SearchData;
+
+// Update the pv of a node after joice raised alpha (triangular pv table)
+pub fn update_pv(pv: &mut Vec<ChessMove>, joice: ChessMove, child_pv: &[ChessMove]) {
+    pv.clear();
+    pv.push(joice);
+    pv.extend_from_slice(child_pv);
+}
+
+// A pv ends early if a node has been taken from the transposition table.
+// Complete it with the best moves of the current search, as long as they stay legal and do not repeat.
+pub fn complete_pv(board: &Board, pv: &mut Vec<ChessMove>, search_data: &SearchData, max_length: usize) {
+    let mut board = *board;
+    let mut visited: HashSet<u64> = HashSet::new();
+    visited.insert(board.get_hash());
+
+    let mut legal_moves = 0;
+    for joice in pv.iter() {
+        if !board.legal(*joice) { break }
+
+        board = board.make_move_new(*joice);
+        visited.insert(board.get_hash());
+        legal_moves += 1;
+    }
+
+    pv.truncate(legal_moves);
+
+    while pv.len() < max_length {
+        let Some(joice) = search_data.best_moves.get(&board.get_hash()).map(|joice| *joice) else { break };
+
+        if !board.legal(joice) { break }
+
+        let next = board.make_move_new(joice);
+        if !visited.insert(next.get_hash()) { break }
+
+        pv.push(joice);
+        board = next;
+    }
+}
+
+#[test]
+fn test_complete_pv() {
+    use std::str::FromStr;
+
+    let search_data = SearchData::new(1);
+    let board = Board::default();
+
+    let e2e4 = ChessMove::from_str("e2e4").unwrap();
+    let e7e5 = ChessMove::from_str("e7e5").unwrap();
+    let g1f3 = ChessMove::from_str("g1f3").unwrap();
+
+    let after_e2e4 = board.make_move_new(e2e4);
+    let after_e7e5 = after_e2e4.make_move_new(e7e5);
+
+    search_data.best_moves.insert(after_e2e4.get_hash(), e7e5);
+    search_data.best_moves.insert(after_e7e5.get_hash(), g1f3);
+
+    let mut pv = vec![e2e4];
+    complete_pv(&board, &mut pv, &search_data, 2);
+    assert_eq!(pv, vec![e2e4, e7e5]);
+
+    let mut pv = vec![e2e4];
+    complete_pv(&board, &mut pv, &search_data, 10);
+    assert_eq!(pv, vec![e2e4, e7e5, g1f3]);
+
+    // illegal moves are removed
+    let mut pv = vec![e2e4, g1f3];
+    complete_pv(&board, &mut pv, &search_data, 10);
+    assert_eq!(pv, vec![e2e4, e7e5, g1f3]);
+}
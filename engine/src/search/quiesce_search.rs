@@ -1,66 +1,78 @@
-use chess::Board;
-use crate::evaluation::evaluate;
+use chess::{Board, Color};
+use crate::evaluation::{evaluate_with_draw_score, is_dead_position};
 use crate::search::move_order::get_move_order_captures;
-use crate::search::NodeType;
+use crate::search::{NodeType, SearchData};
 
-pub fn quiesce_search_max(board: Board, mut alpha: i32, beta: i32) -> (i32, NodeType) {
+// the evaluation is white relative, the search relative to the side to move. Draws include the contempt.
+pub fn evaluate_relative(board: &Board, search_data: &SearchData) -> i32 {
+    let score = evaluate_with_draw_score(board, search_data.draw_score(Color::White));
 
-    let stand_pat = evaluate(&board);
-
-    if stand_pat >= beta { return (beta, NodeType::CUT); }
-    if stand_pat > alpha { alpha = stand_pat; }
-
-    let moves = get_move_order_captures(&board);
-
-    if moves.len() == 0 { return (stand_pat, NodeType::PV) }
-
-    let mut value = stand_pat;
-
-    for joice in moves {
+    if board.side_to_move() == Color::White { score } else { -score }
+}
 
-        let copy = board.make_move_new(joice);
+// search captures until the position is quiet, fail-soft with scores relative to the side to move
+pub fn quiesce_search(board: Board, mut alpha: i32, beta: i32, ply: u8, search_data: &SearchData) -> (i32, NodeType) {
+    search_data.visit_quiescence_node(ply);
 
-        let (score, _) = quiesce_search_min(copy, alpha, beta);
+    if is_dead_position(&board) { return (search_data.draw_score(board.side_to_move()), NodeType::PV) }
 
-        value = value.max(score);
+    // the side to move does not have to capture
+    let stand_pat = evaluate_relative(&board, search_data);
 
-        if value >= beta {
-            return (beta, NodeType::ALL)
-        }
+    if stand_pat >= beta { return (stand_pat, NodeType::CUT) }
 
-        alpha = alpha.max(value);
+    let mut node_type = NodeType::ALL;
+    if stand_pat > alpha {
+        alpha = stand_pat;
+        node_type = NodeType::PV;
     }
 
-    (value, NodeType::PV)
-}
-
-pub fn quiesce_search_min(board: Board, alpha: i32, mut beta: i32) -> (i32, NodeType) {
-
-    let stand_pat = evaluate(&board);
-
-    if stand_pat <= alpha { return (alpha, NodeType::CUT); }
-    if stand_pat < beta { beta = stand_pat; }
+    let mut best_score = stand_pat;
 
-    let moves = get_move_order_captures(&board);
+    for joice in get_move_order_captures(&board) {
 
-    if moves.len() == 0 { return (stand_pat, NodeType::PV) }
+        let copy = board.make_move_new(joice);
 
-    let mut value = stand_pat;
+        let (score, _) = quiesce_search(copy, -beta, -alpha, ply.saturating_add(1), search_data);
+        let score = -score;
 
-    for joice in moves {
+        if score >= beta { return (score, NodeType::CUT) }
 
-        let copy = board.make_move_new(joice);
+        best_score = best_score.max(score);
 
-        let (score, _) = quiesce_search_max(copy, alpha, beta);
-        value = value.min(score);
-
-        if value <= alpha {
-            return (alpha, NodeType::ALL);
+        if score > alpha {
+            alpha = score;
+            node_type = NodeType::PV;
         }
+    }
 
-        beta = beta.min(value);
+    (best_score, node_type)
+}
 
+#[test]
+fn test_draw_score() {
+    use std::str::FromStr;
+
+    // white is the engine and avoids draws
+    let search_data = SearchData::new(1);
+    search_data.draw_score.store(-25, std::sync::atomic::Ordering::Relaxed);
+
+    let draws = [
+        // stalemate
+        "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1",
+        // dead position
+        "8/8/4k3/8/8/3K4/5B2/8 w - - 0 1",
+        // two knights can not force a mate
+        "8/8/4k3/8/8/3K4/3NN3/8 w - - 0 1",
+        // wrong bishop
+        "7k/8/7P/8/8/3K4/8/5B2 b - - 0 1",
+    ];
+
+    for fen in draws {
+        let board = Board::from_str(fen).unwrap();
+
+        let (score, _) = quiesce_search(board, -1000, 1000, 0, &search_data);
+        assert_eq!(score, search_data.draw_score(board.side_to_move()), "{fen}");
+        assert_ne!(score, 0);
     }
-
-    (value, NodeType::PV)
 }
@@ -0,0 +1,79 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+const MAX_DEPTH: usize = 64;
+const MAX_MOVES: usize = 64;
+
+// default parameters of the logarithmic formula
+const BASE: f64 = 0.75;
+const DIVISOR: f64 = 2.25;
+
+// the first moves and shallow nodes are never reduced
+const MIN_DEPTH: usize = 3;
+const MIN_MOVE_INDEX: usize = 3;
+
+/// Late move reductions in plies, indexed by the depth left and the index of the move in the move order.
+/// The values can be changed while the engine is running, e.g. by a tuner.
+pub struct ReductionTable {
+    table: [[AtomicU8; MAX_MOVES]; MAX_DEPTH],
+}
+
+impl Default for ReductionTable {
+    fn default() -> Self {
+        ReductionTable::new(BASE, DIVISOR)
+    }
+}
+
+impl ReductionTable {
+    /// reduction = base + ln(depth) * ln(move index) / divisor
+    pub fn new(base: f64, divisor: f64) -> ReductionTable {
+        let table = ReductionTable {
+            table: std::array::from_fn(|_| std::array::from_fn(|_| AtomicU8::new(0))),
+        };
+        table.set_formula(base, divisor);
+
+        table
+    }
+
+    pub fn set_formula(&self, base: f64, divisor: f64) {
+        for depth in MIN_DEPTH..MAX_DEPTH {
+            for move_index in MIN_MOVE_INDEX..MAX_MOVES {
+                let reduction = base + (depth as f64).ln() * (move_index as f64).ln() / divisor;
+                self.set(depth as u8, move_index, reduction.max(0.0) as u8);
+            }
+        }
+    }
+
+    pub fn set(&self, depth_left: u8, move_index: usize, reduction: u8) {
+        if let Some(entry) = self.table.get(depth_left as usize).and_then(|row| row.get(move_index)) {
+            entry.store(reduction, Ordering::Relaxed);
+        }
+    }
+
+    /// The reduction never drops the search into the quiescence search directly
+    pub fn reduction(&self, depth_left: u8, move_index: usize) -> u8 {
+        let depth = (depth_left as usize).min(MAX_DEPTH - 1);
+        let move_index = move_index.min(MAX_MOVES - 1);
+
+        self.table[depth][move_index].load(Ordering::Relaxed).min(depth_left.saturating_sub(2))
+    }
+}
+
+#[test]
+fn test_reduction_table() {
+    let table = ReductionTable::default();
+
+    assert_eq!(table.reduction(2, 20), 0);
+    assert_eq!(table.reduction(8, 1), 0);
+    assert!(table.reduction(8, 10) >= 1);
+
+    // later moves and deeper nodes are reduced more
+    assert!(table.reduction(20, 40) >= table.reduction(8, 10));
+    assert!(table.reduction(200, 200) >= table.reduction(20, 40));
+
+    // a reduced search keeps at least one ply
+    table.set(5, 10, 10);
+    assert_eq!(table.reduction(5, 10), 3);
+
+    table.set_formula(0.0, 1.0);
+    assert!(table.reduction(20, 40) > ReductionTable::default().reduction(20, 40));
+}
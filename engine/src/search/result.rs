@@ -0,0 +1,87 @@
+use std::time::Duration;
+use chess::{ChessMove, Color};
+use crate::evaluation::{CONSIDERED_MATE, MATE_SCORE};
+use crate::search::SearchStats;
+
+/// Score from the point of view of the side to move
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Score {
+    Centipawns(i32),
+    /// Moves until mate, negative if the side to move gets mated
+    Mate(i32),
+}
+
+impl Score {
+    /// Convert a white relative engine score
+    pub fn new(score: i32, side_to_move: Color) -> Score {
+        Score::relative(if side_to_move == Color::White { score } else { -score })
+    }
+
+    // convert an engine score relative to the side to move
+    pub(crate) fn relative(score: i32) -> Score {
+        if score.abs() <= CONSIDERED_MATE {
+            return Score::Centipawns(score);
+        }
+
+        // every ply on the way to the mate costs one point
+        let plies = MATE_SCORE - score.abs() + 1;
+        let moves = (plies + 1) / 2;
+
+        Score::Mate(if score > 0 { moves } else { -moves })
+    }
+}
+
+/// A line of play starting with a root move
+#[derive(Clone, Debug)]
+pub struct PvLine {
+    pub score: Score,
+    pub pv: Vec<ChessMove>,
+}
+
+/// Outcome of a search, either of a single completed depth or of the whole search
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    pub best_move: ChessMove,
+    pub score: Score,
+    /// Last completed depth, 0 for opening book moves
+    pub depth: u8,
+    pub pv: Vec<ChessMove>,
+    /// The best lines in MultiPV mode, sorted best first. The first line equals the pv.
+    pub lines: Vec<PvLine>,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    pub stats: SearchStats,
+    pub from_book: bool,
+}
+
+impl SearchResult {
+    /// The expected reply of the opponent, which can be used for pondering
+    pub fn ponder_move(&self) -> Option<ChessMove> {
+        self.pv.get(1).copied()
+    }
+
+    pub fn book_move(joice: ChessMove) -> SearchResult {
+        SearchResult {
+            best_move: joice,
+            score: Score::Centipawns(0),
+            depth: 0,
+            pv: vec![joice],
+            lines: vec![PvLine { score: Score::Centipawns(0), pv: vec![joice] }],
+            nodes: 0,
+            elapsed: Duration::ZERO,
+            stats: SearchStats::default(),
+            from_book: true,
+        }
+    }
+}
+
+#[test]
+fn test_score() {
+    assert_eq!(Score::new(120, Color::White), Score::Centipawns(120));
+    assert_eq!(Score::new(120, Color::Black), Score::Centipawns(-120));
+
+    assert_eq!(Score::new(MATE_SCORE, Color::White), Score::Mate(1));
+    assert_eq!(Score::new(MATE_SCORE - 2, Color::White), Score::Mate(2));
+    assert_eq!(Score::new(-MATE_SCORE + 1, Color::White), Score::Mate(-1));
+    assert_eq!(Score::new(-MATE_SCORE + 1, Color::Black), Score::Mate(1));
+}
@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+/// Work done by a search, counted over all depths up to the last completed one
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SearchStats {
+    /// Nodes of the quiescence search, they are part of the total nodes
+    pub qnodes: u64,
+    pub tt_probes: u64,
+    pub tt_hits: u64,
+    /// Probes whose stored score could be used without searching
+    pub tt_cutoffs: u64,
+    pub beta_cutoffs: u64,
+    /// Beta cutoffs caused by the first move searched, a measure of the move ordering
+    pub first_move_cutoffs: u64,
+    /// Deepest ply reached, including the quiescence search
+    pub seldepth: u8,
+    /// Nodes of the last depth divided by the nodes of the depth before
+    pub branching_factor: f64,
+}
+
+impl SearchStats {
+    pub fn tt_hit_rate(&self) -> f64 {
+        self.tt_hits as f64 / self.tt_probes.max(1) as f64
+    }
+
+    pub fn first_move_cutoff_rate(&self) -> f64 {
+        self.first_move_cutoffs as f64 / self.beta_cutoffs.max(1) as f64
+    }
+}
+
+// counters shared by all search threads
+#[derive(Default)]
+pub(crate) struct SearchCounters {
+    qnodes: AtomicU64,
+    tt_probes: AtomicU64,
+    tt_hits: AtomicU64,
+    tt_cutoffs: AtomicU64,
+    beta_cutoffs: AtomicU64,
+    first_move_cutoffs: AtomicU64,
+    seldepth: AtomicU8,
+}
+
+impl SearchCounters {
+    pub fn reset(&self) {
+        for counter in [&self.qnodes, &self.tt_probes, &self.tt_hits, &self.tt_cutoffs, &self.beta_cutoffs, &self.first_move_cutoffs] {
+            counter.store(0, Ordering::Relaxed);
+        }
+
+        self.seldepth.store(0, Ordering::Relaxed);
+    }
+
+    pub fn qnode(&self) {
+        self.qnodes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn tt_probe(&self, hit: bool) {
+        self.tt_probes.fetch_add(1, Ordering::Relaxed);
+        self.tt_hits.fetch_add(hit as u64, Ordering::Relaxed);
+    }
+
+    pub fn tt_cutoff(&self) {
+        self.tt_cutoffs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // move_index is the position of the move, which caused the cutoff, in the move order
+    pub fn beta_cutoff(&self, move_index: usize) {
+        self.beta_cutoffs.fetch_add(1, Ordering::Relaxed);
+
+        if move_index == 0 {
+            self.first_move_cutoffs.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn reach_ply(&self, ply: u8) {
+        self.seldepth.fetch_max(ply, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> SearchStats {
+        SearchStats {
+            qnodes: self.qnodes.load(Ordering::Relaxed),
+            tt_probes: self.tt_probes.load(Ordering::Relaxed),
+            tt_hits: self.tt_hits.load(Ordering::Relaxed),
+            tt_cutoffs: self.tt_cutoffs.load(Ordering::Relaxed),
+            beta_cutoffs: self.beta_cutoffs.load(Ordering::Relaxed),
+            first_move_cutoffs: self.first_move_cutoffs.load(Ordering::Relaxed),
+            seldepth: self.seldepth.load(Ordering::Relaxed),
+            branching_factor: 0.0,
+        }
+    }
+}
+
+#[test]
+fn test_search_counters() {
+    let counters = SearchCounters::default();
+
+    counters.qnode();
+    counters.tt_probe(true);
+    counters.tt_probe(true);
+    counters.tt_cutoff();
+    counters.tt_probe(false);
+    counters.beta_cutoff(0);
+    counters.beta_cutoff(3);
+    counters.reach_ply(7);
+    counters.reach_ply(4);
+
+    let stats = counters.snapshot();
+
+    assert_eq!(stats.qnodes, 1);
+    assert_eq!((stats.tt_probes, stats.tt_hits, stats.tt_cutoffs), (3, 2, 1));
+    assert_eq!((stats.beta_cutoffs, stats.first_move_cutoffs), (2, 1));
+    assert_eq!(stats.seldepth, 7);
+    assert_eq!(stats.first_move_cutoff_rate(), 0.5);
+
+    counters.reset();
+    assert_eq!(counters.snapshot(), SearchStats::default());
+}
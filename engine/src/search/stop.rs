@@ -0,0 +1,138 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::thread::Thread;
+use std::time::Instant;
+
+#[derive(Default)]
+struct StopState {
+    // stop all threads immediately
+    hard_stop: AtomicBool,
+    // finish the current depth, if the previous one is not representative
+    soft_stop: AtomicBool,
+    current_depth: AtomicU8,
+    // the clock does not run while pondering
+    pondering: AtomicBool,
+    // when the clock started, None until the ponder hit
+    clock_start: Mutex<Option<Instant>>,
+    // the caller has reset the handle before starting the search on another thread
+    prepared: AtomicBool,
+    timer: Mutex<Option<Thread>>,
+}
+
+/// Handle to stop the search of one engine, it can be cloned and sent to other threads
+#[derive(Clone, Default)]
+pub struct StopHandle {
+    state: Arc<StopState>,
+}
+
+impl StopHandle {
+    pub fn new() -> StopHandle {
+        StopHandle::default()
+    }
+
+    /// Stop the running search as if its time was up.
+    /// The best move of the last completed depth will be returned.
+    pub fn stop(&self) {
+        self.state.soft_stop.store(true, Ordering::SeqCst);
+
+        // if at least depth 2 is searched hard stop
+        if self.state.current_depth.load(Ordering::Relaxed) > 1 {
+            self.state.hard_stop.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// The opponent played the expected move, continue the ponder search as a normal search.
+    /// Its time limit starts now.
+    pub fn ponder_hit(&self) {
+        *self.state.clock_start.lock().unwrap() = Some(Instant::now());
+        self.state.pondering.store(false, Ordering::SeqCst);
+
+        if let Some(timer) = self.state.timer.lock().unwrap().as_ref() {
+            timer.unpark();
+        }
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.state.pondering.load(Ordering::SeqCst)
+    }
+
+    /// Reset the handle for a search which is about to be started on another thread.
+    /// A stop or ponder hit sent after this call applies to that search, even if it has not started yet.
+    pub fn prepare_search(&self, pondering: bool) {
+        self.reset(pondering);
+        self.state.prepared.store(true, Ordering::SeqCst);
+    }
+
+    // called by the search, requests for a prepared search are kept
+    pub(crate) fn start_search(&self, pondering: bool) {
+        if !self.state.prepared.swap(false, Ordering::SeqCst) {
+            self.reset(pondering);
+        }
+    }
+
+    fn reset(&self, pondering: bool) {
+        self.state.hard_stop.store(false, Ordering::SeqCst);
+        self.state.soft_stop.store(false, Ordering::SeqCst);
+        self.state.current_depth.store(0, Ordering::Relaxed);
+        *self.state.clock_start.lock().unwrap() = (!pondering).then(Instant::now);
+        self.state.pondering.store(pondering, Ordering::SeqCst);
+    }
+
+    pub(crate) fn clock_start(&self) -> Option<Instant> {
+        *self.state.clock_start.lock().unwrap()
+    }
+
+    pub(crate) fn set_timer(&self, timer: Option<Thread>) {
+        *self.state.timer.lock().unwrap() = timer;
+    }
+
+    pub(crate) fn set_depth(&self, depth: u8) {
+        self.state.current_depth.store(depth, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_hard_stopped(&self) -> bool {
+        self.state.hard_stop.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn is_soft_stopped(&self) -> bool {
+        self.state.soft_stop.load(Ordering::SeqCst)
+    }
+}
+
+#[test]
+fn test_stop_handle() {
+    let handle = StopHandle::new();
+    let other = handle.clone();
+
+    // depth 1 is always completed
+    handle.set_depth(1);
+    other.stop();
+    assert!(handle.is_soft_stopped());
+    assert!(!handle.is_hard_stopped());
+
+    handle.reset(false);
+    handle.set_depth(4);
+    other.stop();
+    assert!(handle.is_hard_stopped());
+
+    // handles of other engines are not affected
+    assert!(!StopHandle::new().is_hard_stopped());
+
+    handle.reset(true);
+    assert!(handle.is_pondering());
+    assert_eq!(handle.clock_start(), None);
+    let before_hit = Instant::now();
+    other.ponder_hit();
+    assert!(!handle.is_pondering());
+    assert!(handle.clock_start().unwrap() >= before_hit);
+
+    // a stop sent before the prepared search starts is not lost
+    handle.prepare_search(false);
+    other.stop();
+    handle.start_search(false);
+    assert!(handle.is_soft_stopped());
+
+    // the next search starts without the old stop
+    handle.start_search(false);
+    assert!(!handle.is_soft_stopped());
+}
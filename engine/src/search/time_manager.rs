@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+// expected number of moves until the end of the game, if the clock does not tell
+const MAX_MOVES_LEFT: u32 = 50;
+const MIN_MOVES_LEFT: u32 = 20;
+
+// parts of the remaining time, which may be used for a single move
+const MAX_SOFT_USAGE: f64 = 0.4;
+const MAX_HARD_USAGE: f64 = 0.75;
+const HARD_LIMIT_FACTOR: u32 = 4;
+
+// an unstable best move or a falling score extend the soft limit by this factor, up to the maximum
+const EXTENSION_FACTOR: f64 = 1.5;
+const MAX_EXTENSION: f64 = 3.0;
+// centipawns the score has to fall to count as a drop
+const SCORE_DROP: i32 = 30;
+
+// a root move dominates, if it is that much better than the second best one
+const DOMINANCE_MARGIN: i32 = 200;
+
+/// Time control of the engine as sent by the gui or the server
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Clock {
+    pub time_left: Duration,
+    pub increment: Duration,
+    /// Moves until the next time control, None if the rest of the game has to be played with the time left
+    pub moves_to_go: Option<u32>,
+}
+
+/// Plans the time of a single move. The search should not start a new depth after the soft limit
+/// and is stopped at the hard limit.
+#[derive(Clone, Debug)]
+pub struct TimeManager {
+    soft_limit: Duration,
+    hard_limit: Duration,
+    extension: f64,
+    fixed: bool,
+}
+
+impl TimeManager {
+    /// Ply is the number of half moves played so far, it is used to estimate the length of the game
+    pub fn new(clock: &Clock, ply: u16, move_overhead: Duration) -> TimeManager {
+        let available = clock.time_left.saturating_sub(move_overhead);
+
+        let estimated_moves_left = MAX_MOVES_LEFT.saturating_sub(ply as u32 / 4).max(MIN_MOVES_LEFT);
+        let moves_left = clock.moves_to_go.unwrap_or(estimated_moves_left).clamp(1, estimated_moves_left);
+
+        // most of the increment can be used, as it is added again after the move
+        let planned = available / moves_left + clock.increment * 3 / 4;
+
+        let soft_limit = planned.min(available.mul_f64(MAX_SOFT_USAGE));
+        let hard_limit = (soft_limit * HARD_LIMIT_FACTOR).min(available.mul_f64(MAX_HARD_USAGE)).max(soft_limit);
+
+        TimeManager { soft_limit, hard_limit, extension: 1.0, fixed: false }
+    }
+
+    /// Search exactly for the given time
+    pub fn fixed(move_time: Duration) -> TimeManager {
+        TimeManager { soft_limit: move_time, hard_limit: move_time, extension: 1.0, fixed: true }
+    }
+
+    pub fn soft_limit(&self) -> Duration {
+        self.soft_limit.mul_f64(self.extension).min(self.hard_limit)
+    }
+
+    pub fn hard_limit(&self) -> Duration {
+        self.hard_limit
+    }
+
+    /// Decide after a completed depth, if the next one should be searched.
+    /// The score change is relative to the previous depth from the point of view of the engine.
+    /// Margin is the distance between the best and the second best root move, None if there is only one legal move.
+    pub fn continue_search(&mut self, elapsed: Duration, best_move_changed: bool, score_change: i32, margin: Option<i32>) -> bool {
+        if self.fixed {
+            return elapsed < self.hard_limit;
+        }
+
+        let Some(margin) = margin else { return false };
+
+        if best_move_changed || score_change <= -SCORE_DROP {
+            self.extension = (self.extension * EXTENSION_FACTOR).min(MAX_EXTENSION);
+        } else {
+            // the position calmed down again
+            self.extension = (self.extension / EXTENSION_FACTOR).max(1.0);
+        }
+
+        // no need to think long about an obvious move
+        if margin >= DOMINANCE_MARGIN && elapsed >= self.soft_limit / 4 {
+            return false;
+        }
+
+        elapsed < self.soft_limit()
+    }
+}
+
+#[cfg(test)]
+fn clock(time_left_ms: u64, increment_ms: u64, moves_to_go: Option<u32>) -> Clock {
+    Clock {
+        time_left: Duration::from_millis(time_left_ms),
+        increment: Duration::from_millis(increment_ms),
+        moves_to_go,
+    }
+}
+
+#[test]
+fn test_bullet() {
+    let overhead = Duration::from_millis(50);
+
+    // 1+0 at the start of the game
+    let time_manager = TimeManager::new(&clock(60_000, 0, None), 0, overhead);
+    assert!(time_manager.soft_limit() > Duration::from_millis(800));
+    assert!(time_manager.soft_limit() < Duration::from_millis(2_000));
+    assert!(time_manager.hard_limit() < Duration::from_secs(8));
+
+    // almost flagging, the overhead is kept free
+    let time_manager = TimeManager::new(&clock(300, 0, None), 80, overhead);
+    assert!(time_manager.hard_limit() < Duration::from_millis(250));
+
+    let time_manager = TimeManager::new(&clock(30, 0, None), 80, overhead);
+    assert_eq!(time_manager.hard_limit(), Duration::ZERO);
+}
+
+#[test]
+fn test_blitz() {
+    // 3+2 in the middle game
+    let time_manager = TimeManager::new(&clock(120_000, 2_000, None), 60, Duration::from_millis(50));
+
+    assert!(time_manager.soft_limit() > Duration::from_secs(4));
+    assert!(time_manager.soft_limit() < Duration::from_secs(6));
+    assert!(time_manager.hard_limit() <= time_manager.soft_limit() * HARD_LIMIT_FACTOR);
+
+    // the increment is mostly used, even if the clock is low
+    let time_manager = TimeManager::new(&clock(3_000, 2_000, None), 60, Duration::from_millis(50));
+    assert!(time_manager.soft_limit() > Duration::from_millis(1_000));
+    assert!(time_manager.hard_limit() < Duration::from_millis(3_000));
+}
+
+#[test]
+fn test_classical() {
+    // 40 moves in 90 minutes, 10 moves left to the time control
+    let time_manager = TimeManager::new(&clock(20 * 60_000, 30_000, Some(10)), 60, Duration::from_millis(50));
+
+    assert!(time_manager.soft_limit() > Duration::from_secs(120));
+    assert!(time_manager.soft_limit() < Duration::from_secs(150));
+
+    // the last move before the time control may not use all of the time
+    let time_manager = TimeManager::new(&clock(60_000, 0, Some(1)), 78, Duration::from_millis(50));
+    assert!(time_manager.hard_limit() < Duration::from_secs(45));
+}
+
+#[test]
+fn test_correspondence() {
+    // three days per move
+    let days = 3 * 24 * 60 * 60 * 1000;
+    let time_manager = TimeManager::new(&clock(days, 0, Some(1)), 20, Duration::from_millis(50));
+
+    assert!(time_manager.soft_limit() > Duration::from_secs(60 * 60));
+    assert!(time_manager.hard_limit() < Duration::from_millis(days));
+}
+
+#[test]
+fn test_continue_search() {
+    let mut time_manager = TimeManager::new(&clock(120_000, 0, None), 20, Duration::ZERO);
+    let soft_limit = time_manager.soft_limit();
+
+    assert!(time_manager.continue_search(soft_limit / 2, false, 0, Some(20)));
+    assert!(!time_manager.continue_search(soft_limit, false, 0, Some(20)));
+
+    // a new best move extends the time
+    assert!(time_manager.continue_search(soft_limit, true, 0, Some(20)));
+    assert!(time_manager.soft_limit() > soft_limit);
+
+    // a falling score too
+    let mut time_manager = TimeManager::new(&clock(120_000, 0, None), 20, Duration::ZERO);
+    assert!(time_manager.continue_search(soft_limit, false, -50, Some(20)));
+
+    // an obvious or forced move is played early
+    let mut time_manager = TimeManager::new(&clock(120_000, 0, None), 20, Duration::ZERO);
+    assert!(!time_manager.continue_search(soft_limit / 2, false, 0, Some(500)));
+    assert!(!time_manager.continue_search(Duration::ZERO, false, 0, None));
+
+    // a fixed move time is used completely
+    let mut time_manager = TimeManager::fixed(Duration::from_secs(1));
+    assert!(time_manager.continue_search(Duration::from_millis(900), false, 0, None));
+    assert!(!time_manager.continue_search(Duration::from_secs(1), false, 0, None));
+}
@@ -4,7 +4,6 @@ use crate::transposition_table::entry::Entry;
 use rayon::prelude::*;
 
 const BUCKET_SIZE: usize = 3;
-const TABLE_SIZE: usize = 3_000_000;
 
 type Bucket = [RwLock<Entry>; BUCKET_SIZE];
 
@@ -49,11 +48,13 @@ pub struct TranspositionTable {
 }
 
 impl TranspositionTable {
-    pub fn new() -> TranspositionTable {
+    pub fn new(size_mb: usize) -> TranspositionTable {
 
-        let mut buckets = Vec::with_capacity(TABLE_SIZE);
+        let table_size = (size_mb * 1024 * 1024 / std::mem::size_of::<Bucket>()).max(1);
 
-        for _ in 0..TABLE_SIZE {
+        let mut buckets = Vec::with_capacity(table_size);
+
+        for _ in 0..table_size {
             let entry = Entry::Empty;
             let bucket: Bucket = core::array::from_fn(|_| RwLock::new(entry.clone()));
 
@@ -66,7 +67,7 @@ impl TranspositionTable {
     }
 
     pub fn get(&self, hash: u64) -> &RwLock<Entry> {
-        let bucket: &Bucket = &self.buckets[hash as usize % TABLE_SIZE];
+        let bucket: &Bucket = &self.buckets[hash as usize % self.buckets.len()];
 
         let entry = bucket.get_by_hash(hash);
 
@@ -86,4 +87,12 @@ impl TranspositionTable {
             }
         });
     }
-}
\ No newline at end of file
+
+    pub fn clear(&self) {
+        self.buckets.par_iter().for_each(|bucket| {
+            for entry in bucket.iter() {
+                *entry.write().unwrap() = Entry::Empty;
+            }
+        });
+    }
+}
@@ -1,4 +1,3 @@
-use engine::Engine;
 use engine::evaluation::{evaluate, MATE_SCORE};
 use chess::{Board, ChessMove};
 use std::str::FromStr;
@@ -10,4 +9,17 @@ fn test_mate() {
     let board = board.make_move_new(ChessMove::from_str("a6a8").unwrap());
 
     assert_eq!(evaluate(&board), MATE_SCORE);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_material_draws() {
+    use engine::evaluation::DRAW_SCORE;
+
+    // a bishop up, but no way to mate
+    assert_eq!(evaluate(&Board::from_str("8/8/4k3/8/8/3K4/5B2/8 w - - 0 1").unwrap()), DRAW_SCORE);
+    assert_eq!(evaluate(&Board::from_str("8/8/4kn2/8/8/3K4/5B2/8 w - - 0 1").unwrap()), DRAW_SCORE);
+    // wrong bishop with the defending king in the corner
+    assert_eq!(evaluate(&Board::from_str("7k/8/7P/8/8/3K4/8/5B2 w - - 0 1").unwrap()), DRAW_SCORE);
+
+    assert!(evaluate(&Board::from_str("7k/8/7P/8/8/3K4/8/4B3 w - - 0 1").unwrap()) > DRAW_SCORE);
+}
@@ -0,0 +1,40 @@
+use chess::Board;
+use engine::perft::perft;
+use std::str::FromStr;
+
+// reference counts from https://www.chessprogramming.org/Perft_Results
+fn check(fen: &str, counts: &[u64]) {
+    let board = Board::from_str(fen).unwrap();
+
+    for (depth, &count) in counts.iter().enumerate() {
+        assert_eq!(perft(&board, depth as u8 + 1), count, "perft {} of {fen}", depth + 1);
+    }
+}
+
+#[test]
+fn test_start_position() {
+    check("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[20, 400, 8_902, 197_281]);
+}
+
+#[test]
+fn test_kiwipete() {
+    check("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2_039, 97_862]);
+}
+
+#[test]
+fn test_endgame() {
+    check("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2_812, 43_238]);
+}
+
+#[test]
+fn test_promotions() {
+    check("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9_467]);
+    // the same position with colors reversed
+    check("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1", &[6, 264, 9_467]);
+}
+
+#[test]
+fn test_middle_game() {
+    check("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1_486, 62_379]);
+    check("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", &[46, 2_079, 89_890]);
+}
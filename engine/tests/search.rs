@@ -0,0 +1,281 @@
+use std::time::Duration;
+use chess::ChessMove;
+use engine::{Clock, Engine, EngineConfig, Score, SearchLimits, SearchResult};
+use std::str::FromStr;
+
+#[test]
+fn test_search_result() {
+    let mut engine = Engine::default();
+    engine.load_fen("7k/1R6/R7/8/8/8/8/K7 w - - 0 1").unwrap();
+
+    let result = engine.get_engine_move(&SearchLimits::move_time(Duration::from_secs(1))).unwrap();
+
+    assert_eq!(result.best_move, ChessMove::from_str("a6a8").unwrap());
+    assert_eq!(result.score, Score::Mate(1));
+    assert_eq!(result.pv.first(), Some(&result.best_move));
+    assert!(result.depth >= 1);
+    assert!(result.nodes > 0);
+    assert!(!result.from_book);
+}
+
+#[test]
+fn test_multi_pv() {
+    let mut engine = Engine::default();
+    engine.load_fen("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4").unwrap();
+    engine.set_multi_pv(3);
+
+    let result = engine.iterative_deepening(&SearchLimits::depth(3), &()).unwrap();
+
+    assert_eq!(result.lines.len(), 3);
+    assert_eq!(result.lines[0].pv, result.pv);
+    assert_eq!(result.lines[0].score, Score::Mate(1));
+
+    // the lines are sorted and start with different moves
+    assert_ne!(result.lines[1].pv[0], result.lines[0].pv[0]);
+    assert_ne!(result.lines[2].pv[0], result.lines[1].pv[0]);
+    assert!(!matches!(result.lines[1].score, Score::Mate(1)));
+}
+
+#[test]
+fn test_search_limits() {
+    let mut engine = Engine::default();
+    engine.load_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+
+    let result = engine.iterative_deepening(&SearchLimits::depth(3), &()).unwrap();
+    assert_eq!(result.depth, 3);
+
+    let result = engine.iterative_deepening(&SearchLimits { depth: Some(20), nodes: Some(20_000), ..Default::default() }, &()).unwrap();
+    assert!(result.depth < 20);
+
+    // the node limit stops the search in the middle of a depth
+    assert!(result.nodes >= 20_000);
+
+    let result = engine.iterative_deepening(&SearchLimits::move_time(Duration::from_millis(200)), &()).unwrap();
+    assert!(result.elapsed < Duration::from_secs(5));
+
+    // two seconds on the clock leave well below a second for one move
+    let clock = Clock { time_left: Duration::from_secs(2), increment: Duration::ZERO, moves_to_go: None };
+    let result = engine.iterative_deepening(&SearchLimits::clock(clock), &()).unwrap();
+    assert!(result.elapsed < Duration::from_secs(1));
+
+    // back rank mate
+    engine.load_fen("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1").unwrap();
+
+    let result = engine.iterative_deepening(&SearchLimits::mate(1), &()).unwrap();
+    assert_eq!(result.score, Score::Mate(1));
+    assert_eq!(result.best_move, ChessMove::from_str("d1d8").unwrap());
+    assert_eq!(result.depth, 1);
+}
+
+#[test]
+fn test_independent_stop() {
+    let mut engine = Engine::default();
+    engine.load_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+
+    let stop_handle = engine.stop_handle();
+    let search = std::thread::spawn(move || engine.iterative_deepening(&SearchLimits::infinite(), &()).unwrap());
+
+    // a second engine is neither stopped by nor stops the first one
+    let mut other = Engine::default();
+    other.load_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+    other.stop_handle().stop();
+
+    let result = other.iterative_deepening(&SearchLimits::depth(4), &()).unwrap();
+    assert_eq!(result.depth, 4);
+
+    assert!(!search.is_finished());
+
+    stop_handle.stop();
+    let result = search.join().unwrap();
+    assert!(result.depth >= 1);
+}
+
+#[test]
+fn test_stop_before_search_starts() {
+    let mut engine = Engine::default();
+    engine.load_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+
+    let stop_handle = engine.stop_handle();
+    stop_handle.prepare_search(false);
+
+    // the stop is sent before the search thread has started
+    let search = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        engine.iterative_deepening(&SearchLimits::infinite(), &()).unwrap()
+    });
+    stop_handle.stop();
+
+    // the first depth is always completed
+    assert_eq!(search.join().unwrap().depth, 1);
+}
+
+#[test]
+fn test_ponder() {
+    let mut engine = Engine::default();
+    engine.load_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+
+    let position = engine.get_position();
+    let ponder_move = ChessMove::from_str("f1b5").unwrap();
+    let stop_handle = engine.stop_handle();
+
+    let search = std::thread::spawn(move || engine.ponder(ponder_move, &SearchLimits::move_time(Duration::from_millis(100)), &()).unwrap());
+
+    // the move time does not run out while pondering
+    std::thread::sleep(Duration::from_millis(500));
+    assert!(!search.is_finished());
+    assert!(stop_handle.is_pondering());
+
+    stop_handle.ponder_hit();
+    let result = search.join().unwrap();
+
+    // the reply of black has been searched
+    let board = chess::Board::from_str(&position).unwrap().make_move_new(ponder_move);
+    assert!(board.legal(result.best_move));
+    assert!(!stop_handle.is_pondering());
+}
+
+#[test]
+fn test_ponder_hit_before_search_starts() {
+    let mut engine = Engine::default();
+    engine.load_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+
+    let stop_handle = engine.stop_handle();
+    stop_handle.prepare_search(true);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        let result = engine.ponder(ChessMove::from_str("f1b5").unwrap(), &SearchLimits::move_time(Duration::from_millis(100)), &());
+        sender.send(result.unwrap()).unwrap();
+    });
+    stop_handle.ponder_hit();
+
+    // the move time runs from the ponder hit, the search does not keep on pondering
+    assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+    assert!(!stop_handle.is_pondering());
+}
+
+#[test]
+fn test_search_observer() {
+    use std::sync::Mutex;
+    use engine::{SearchObserver, SearchResult};
+
+    #[derive(Default)]
+    struct Recorder {
+        iterations: Mutex<Vec<u8>>,
+        best_moves: Mutex<Vec<ChessMove>>,
+        scored: Mutex<Vec<(u8, ChessMove)>>,
+        finished: Mutex<Option<ChessMove>>,
+    }
+
+    impl SearchObserver for Recorder {
+        fn iteration_completed(&self, result: &SearchResult) {
+            self.iterations.lock().unwrap().push(result.depth);
+        }
+
+        fn new_best_move(&self, _depth: u8, joice: ChessMove, _score: Score) {
+            self.best_moves.lock().unwrap().push(joice);
+        }
+
+        fn root_move_scored(&self, depth: u8, joice: ChessMove, _score: Score) {
+            self.scored.lock().unwrap().push((depth, joice));
+        }
+
+        fn search_finished(&self, result: &SearchResult) {
+            *self.finished.lock().unwrap() = Some(result.best_move);
+        }
+    }
+
+    let mut engine = Engine::default();
+    engine.load_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+
+    let recorder = Recorder::default();
+    let result = engine.iterative_deepening(&SearchLimits::depth(3), &recorder).unwrap();
+
+    assert_eq!(*recorder.iterations.lock().unwrap(), vec![1, 2, 3]);
+    assert_eq!(recorder.best_moves.lock().unwrap().last(), Some(&result.best_move));
+    assert_eq!(*recorder.finished.lock().unwrap(), Some(result.best_move));
+
+    // every legal root move is scored on every depth
+    let scored = recorder.scored.lock().unwrap();
+    assert_eq!(scored.iter().filter(|(depth, _)| *depth == 3).count(), 27);
+    assert!(scored.contains(&(3, result.best_move)));
+}
+
+#[test]
+fn test_engine_errors() {
+    use engine::EngineError;
+
+    let mut engine = Engine::default();
+
+    assert_eq!(engine.load_fen("not a fen"), Err(EngineError::InvalidFen("not a fen".to_string())));
+    assert_eq!(engine.make_move("e2e5".to_string()), Err(EngineError::IllegalMove("e2e5".to_string())));
+    assert_eq!(engine.make_move("hello".to_string()), Err(EngineError::UnparsableMove("hello".to_string())));
+
+    // nothing has been changed by the errors
+    assert!(engine.move_history().is_empty());
+    engine.make_move("e2e4".to_string()).unwrap();
+
+    engine.load_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+    assert_eq!(engine.iterative_deepening(&SearchLimits::depth(3), &()).unwrap_err(), EngineError::NoLegalMoves);
+}
+
+#[test]
+fn test_draw_detection() {
+    let mut engine = Engine::default();
+
+    // every move of the rook ends the game by the fifty move rule
+    engine.load_fen("8/8/8/4k3/8/8/4K3/4R3 w - - 99 80").unwrap();
+    let result = engine.iterative_deepening(&SearchLimits::depth(3), &()).unwrap();
+    assert_eq!(result.score, Score::Centipawns(0));
+
+    // the knight going back repeats a position of the game for the third time
+    engine.set_position(None, &["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6"]).unwrap();
+    engine.set_multi_pv(64);
+
+    let result = engine.iterative_deepening(&SearchLimits::depth(2), &()).unwrap();
+    let repetition = result.lines.iter().find(|line| line.pv[0] == ChessMove::from_str("f3g1").unwrap()).unwrap();
+    assert_eq!(repetition.score, Score::Centipawns(0));
+    assert_eq!(repetition.pv.len(), 1);
+
+    // with contempt a draw is worse for the engine
+    let mut engine = Engine::new(EngineConfig::builder().contempt(30).hash_mb(16).build().unwrap());
+    engine.set_position(None, &["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6"]).unwrap();
+    engine.set_multi_pv(64);
+
+    let result = engine.iterative_deepening(&SearchLimits::depth(2), &()).unwrap();
+    let repetition = result.lines.iter().find(|line| line.pv[0] == ChessMove::from_str("f3g1").unwrap()).unwrap();
+    assert_eq!(repetition.score, Score::Centipawns(-30));
+}
+
+#[test]
+fn test_bench() {
+    use engine::bench::bench;
+
+    // the node count is the signature of the search and must not depend on timing
+    let first = bench(2).unwrap();
+    let second = bench(2).unwrap();
+
+    assert!(first.nodes > 0);
+    assert_eq!(first.nodes, second.nodes);
+}
+
+#[test]
+fn test_search_stats() {
+    let engine = Engine::default();
+
+    let iterations = std::sync::Mutex::new(Vec::new());
+    let result = engine.iterative_deepening(&SearchLimits::depth(4), &|info: &SearchResult| iterations.lock().unwrap().push(info.stats)).unwrap();
+    let iterations = iterations.into_inner().unwrap();
+
+    assert_eq!(iterations.len(), 4);
+    assert!(iterations.windows(2).all(|pair| pair[0].qnodes <= pair[1].qnodes));
+    assert!(iterations[1..].iter().all(|stats| stats.branching_factor > 1.0));
+
+    let stats = result.stats;
+    assert!(stats.qnodes > 0 && stats.qnodes < result.nodes);
+    assert!(stats.tt_hits > 0 && stats.tt_hits <= stats.tt_probes);
+    assert!(stats.tt_cutoffs <= stats.tt_hits);
+    assert!(stats.first_move_cutoffs > 0 && stats.first_move_cutoffs <= stats.beta_cutoffs);
+    assert!(stats.seldepth >= 4);
+}
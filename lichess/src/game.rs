@@ -1,7 +1,7 @@
 use std::time::Duration;
 use chess::Color;
 use serde::{Deserialize, Serialize};
-use engine::Engine;
+use engine::{Clock, Engine, EngineConfig, EngineError, SearchLimits};
 
 #[derive(Serialize, Deserialize, Debug)]
 struct GameInfo {
@@ -52,7 +52,7 @@ enum GameState {
 
 pub async fn listen_to_game(game_id: String) {
 
-    let mut engine = Engine::new();
+    let mut engine = Engine::new(engine_config());
 
     let client = reqwest::Client::new();
     let mut req = client.get(format!("https://lichess.org/api/bot/game/stream/{}", game_id))
@@ -68,16 +68,11 @@ pub async fn listen_to_game(game_id: String) {
         match game_info.state {
             GameState::StateEvent { ref moves, wtime, btime, winc, binc } => {
 
-                if let Some(fen) = &game_info.initial_fen {
-                    if fen != "startpos" {
-                        engine.load_fen(fen).expect("Valid fen");
-                    }
-                }
+                let fen = game_info.initial_fen.as_deref().filter(|&fen| fen != "startpos");
+                let moves: Vec<&str> = moves.split(' ').filter(|joice| !joice.is_empty()).collect();
 
-                for joice in moves.split(' ') {
-                    if joice != "" {
-                        engine.make_move(joice.to_string());
-                    }
+                if let Err(error) = engine.set_position(fen, &moves) {
+                    log::error!("Could not load the game: {error}");
                 }
 
                 log::info!("Loaded Game: {}", engine.get_position());
@@ -93,9 +88,10 @@ pub async fn listen_to_game(game_id: String) {
                     let time_left = Duration::from_millis(time_left as u64);
                     let inc = Duration::from_millis(inc as u64);
 
-                    let recommended_timeout = engine.recommended_timeout(time_left, inc);
-
-                    post_move(&client, game_id.clone(), engine.get_engine_move(recommended_timeout)).await;
+                    match engine_move(&mut engine, time_left, inc) {
+                        Ok(joice) => post_move(&client, game_id.clone(), joice).await,
+                        Err(error) => log::error!("Could not generate a move: {error}")
+                    }
 
                     let now = std::time::Instant::now();
                     engine.do_off_move_stuff();
@@ -117,41 +113,84 @@ pub async fn listen_to_game(game_id: String) {
 
             let game_state: GameState = serde_json::from_slice(&chunk).unwrap();
 
-            match game_state {
-                GameState::StateEvent { moves, wtime, btime, winc, binc } => {
+            if let GameState::StateEvent { moves, wtime, btime, winc, binc } = game_state {
+
+                let moves: Vec<&str> = moves.split(' ').filter(|joice| !joice.is_empty()).collect();
+
+                // update moves in engine, a takeback shortens the move list
+                sync_moves(&mut engine, &moves);
+
+                let my_color = game_info.my_color();
+
+                // check if it is our move
+                if engine.is_my_turn(my_color) {
+
+                    let (time_left, inc) = match my_color {
+                        Color::White => (wtime, winc),
+                        Color::Black => (btime, binc)
+                    };
+                    let time_left = Duration::from_millis(time_left as u64);
+                    let inc = Duration::from_millis(inc as u64);
+
+                    match engine_move(&mut engine, time_left, inc) {
+                        Ok(joice) => post_move(&client, game_id.clone(), joice).await,
+                        Err(error) => log::error!("Could not generate a move: {error}")
+                    }
+
+                    let now = std::time::Instant::now();
+                    engine.do_off_move_stuff();
+                    log::error!("{}", now.elapsed().as_millis());
+                }
+
+            }
+
+        }
 
-                    let moves: Vec<&str> = moves.split(' ').collect();
+    }
+}
 
-                    // update move in engine
-                    engine.make_move(moves.last().unwrap().to_string());
+// the move time of the bot can still be set in the .env file
+fn engine_config() -> EngineConfig {
+    let mut config = EngineConfig::builder();
 
-                    let my_color = game_info.my_color();
+    if let Some(seconds) = dotenv::var("DEFAULT_TIMEOUT_SECS").ok().and_then(|seconds| seconds.parse().ok()) {
+        config = config.default_move_time(Duration::from_secs(seconds));
+    }
 
-                    // check if it is our move
-                    if engine.is_my_turn(my_color) {
+    config.build().expect("The config of the bot is valid")
+}
 
-                        let (time_left, inc) = match my_color {
-                            Color::White => (wtime, winc),
-                            Color::Black => (btime, binc)
-                        };
-                        let time_left = Duration::from_millis(time_left as u64);
-                        let inc = Duration::from_millis(inc as u64);
+fn engine_move(engine: &mut Engine, time_left: Duration, inc: Duration) -> Result<String, EngineError> {
+    // games without a clock report an almost infinite time
+    let limits = if time_left.as_secs() > 2_000_000 {
+        SearchLimits::move_time(engine.config().default_move_time())
+    } else {
+        SearchLimits::clock(Clock { time_left, increment: inc, moves_to_go: None })
+    };
 
-                        let recommended_timeout = engine.recommended_timeout(time_left, inc);
+    Ok(engine.get_engine_move(&limits)?.best_move.to_string())
+}
 
-                        post_move(&client, game_id.clone(), engine.get_engine_move(recommended_timeout)).await;
+// take back moves until the engine is on the same line as lichess, then play the new moves
+fn sync_moves(engine: &mut Engine, moves: &[&str]) {
+    loop {
+        let history = engine.move_history();
 
-                        let now = std::time::Instant::now();
-                        engine.do_off_move_stuff();
-                        log::error!("{}", now.elapsed().as_millis());
-                    }
+        let same_line = history.len() <= moves.len()
+            && history.iter().zip(moves).all(|(made, joice)| made.to_string() == *joice);
 
-                },
-                _ => {}
+        if same_line {
+            for joice in &moves[history.len()..] {
+                if let Err(error) = engine.make_move(joice.to_string()) {
+                    log::error!("Could not follow the game: {error}");
+                    break;
+                }
             }
 
+            return;
         }
 
+        engine.undo_move();
     }
 }
 
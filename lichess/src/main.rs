@@ -1,7 +1,6 @@
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use dotenv;
 use env_logger::Env;
 
 mod event_stream;
@@ -23,7 +22,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     loop {
         'l: loop {
             {
-                if open_games.lock().unwrap().len() > 0 {
+                if !open_games.lock().unwrap().is_empty() {
                     break 'l;
                 }
             }
@@ -46,14 +46,24 @@ impl BuildNode {
             self.children.iter().position(|c| c.1 == child).unwrap()
         };
 
-        return &mut self.children[index].1;
+        &mut self.children[index].1
+    }
+
+    pub fn get_best_move(&self) -> Option<String> {
+        let joice = self.children.iter().max()?;
+
+        Some(joice.1.joice.clone())
+    }
+
+    pub fn get_node_by_move(&self, joice: &str) -> Option<&BuildNode> {
+        self.children.iter().find(|child| child.1.joice == joice).map(|child| &child.1)
     }
 }
 
 impl Node {
     pub fn get_best_node(&self) -> Option<&NodeCount> {
         let best = self.children.iter().filter(|a| a.0 >= 2).collect::<Vec<&NodeCount>>();
-        if best.len() == 0 { return None }
+        if best.is_empty() { return None }
 
         let mut games_in_best = 0;
         for game in &best {
@@ -64,11 +74,11 @@ impl Node {
         let mut random_game = rng.gen_range(0..games_in_best);
 
         for game in best {
-            random_game -= game.0;
-
-            if random_game <= 0 {
+            if random_game < game.0 {
                 return Some(game);
             }
+
+            random_game -= game.0;
         }
 
         unreachable!()
@@ -76,7 +86,7 @@ impl Node {
     }
 
     pub fn get_best_move(&self) -> Option<String> {
-        let Some(joice) = self.children.iter().max() else { return None };
+        let joice = self.children.iter().max()?;
 
         Some(joice.1.joice.to_string())
     }
@@ -90,4 +100,14 @@ impl Node {
         None
     }
 
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_get_best_node() {
+    const BOOK: Node = Node { joice: "", children: &[(1, Node { joice: "a2a3", children: &[] }), (3, Node { joice: "e2e4", children: &[] })] };
+
+    // moves played less than twice are never picked
+    for _ in 0..20 {
+        assert_eq!(BOOK.get_best_node().map(|node| node.1.joice), Some("e2e4"));
+    }
+}